@@ -3,6 +3,17 @@ pub use sea_orm_migration::prelude::*;
 mod m20250614_163005_create_sources_table;
 mod m20251001_000000_create_stickers_table;
 mod m20260218_000000_create_brew_downloads_table;
+mod m20261016_000000_alter_counters_to_bigint;
+mod m20261016_000001_create_source_hits_table;
+mod m20261016_000002_create_source_aliases_table;
+mod m20261016_000003_add_utm_to_source_hits;
+mod m20261016_000004_create_source_visitors_table;
+mod m20261016_000005_add_bot_counters;
+mod m20261016_000006_add_stickers_lat_lon_index;
+mod m20261016_000007_add_visibility_to_stickers;
+mod m20261016_000008_add_placed_at_to_stickers;
+mod m20261016_000009_add_country_and_region_to_stickers;
+mod m20261016_000010_add_visitor_days_to_sources;
 
 pub struct Migrator;
 
@@ -13,6 +24,17 @@ impl MigratorTrait for Migrator {
             Box::new(m20250614_163005_create_sources_table::Migration),
             Box::new(m20251001_000000_create_stickers_table::Migration),
             Box::new(m20260218_000000_create_brew_downloads_table::Migration),
+            Box::new(m20261016_000000_alter_counters_to_bigint::Migration),
+            Box::new(m20261016_000001_create_source_hits_table::Migration),
+            Box::new(m20261016_000002_create_source_aliases_table::Migration),
+            Box::new(m20261016_000003_add_utm_to_source_hits::Migration),
+            Box::new(m20261016_000004_create_source_visitors_table::Migration),
+            Box::new(m20261016_000005_add_bot_counters::Migration),
+            Box::new(m20261016_000006_add_stickers_lat_lon_index::Migration),
+            Box::new(m20261016_000007_add_visibility_to_stickers::Migration),
+            Box::new(m20261016_000008_add_placed_at_to_stickers::Migration),
+            Box::new(m20261016_000009_add_country_and_region_to_stickers::Migration),
+            Box::new(m20261016_000010_add_visitor_days_to_sources::Migration),
         ]
     }
 }
@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Widen counters so they cannot overflow i32
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sources::Table)
+                    .modify_column(ColumnDef::new(Sources::Count).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BrewDownloads::Table)
+                    .modify_column(ColumnDef::new(BrewDownloads::Count).big_integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BrewDownloads::Table)
+                    .modify_column(ColumnDef::new(BrewDownloads::Count).integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sources::Table)
+                    .modify_column(ColumnDef::new(Sources::Count).integer())
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Sources {
+    Table,
+    Count,
+}
+
+#[derive(DeriveIden)]
+enum BrewDownloads {
+    Table,
+    Count,
+}
@@ -0,0 +1,106 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{big_integer, integer, timestamp},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One row per recorded increment of a source
+        manager
+            .create_table(
+                Table::create()
+                    .table(SourceHits::Table)
+                    .if_not_exists()
+                    .col(
+                        big_integer(SourceHits::Id)
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(integer(SourceHits::SourceId).not_null())
+                    .col(big_integer(SourceHits::Count).default(1).not_null())
+                    .col(
+                        timestamp(SourceHits::CreatedAt)
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_source_hits_source_id")
+                            .from(SourceHits::Table, SourceHits::SourceId)
+                            .to(Sources::Table, Sources::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Time series queries filter by source and time range
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_source_hits_source_id_created_at")
+                    .table(SourceHits::Table)
+                    .col(SourceHits::SourceId)
+                    .col(SourceHits::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_source_hits_created_at")
+                    .table(SourceHits::Table)
+                    .col(SourceHits::CreatedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_source_hits_created_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_source_hits_source_id_created_at")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(SourceHits::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SourceHits {
+    Table,
+    Id,
+    SourceId,
+    Count,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Sources {
+    Table,
+    Id,
+}
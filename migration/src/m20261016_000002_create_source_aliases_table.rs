@@ -0,0 +1,81 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{integer, pk_auto, string_uniq, timestamp},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Maps an alternative name to the source that should receive its hits
+        manager
+            .create_table(
+                Table::create()
+                    .table(SourceAliases::Table)
+                    .if_not_exists()
+                    .col(pk_auto(SourceAliases::Id))
+                    .col(string_uniq(SourceAliases::Alias))
+                    .col(integer(SourceAliases::SourceId).not_null())
+                    .col(
+                        timestamp(SourceAliases::CreatedAt)
+                            .default(Expr::current_timestamp())
+                            .not_null(),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_source_aliases_source_id")
+                            .from(SourceAliases::Table, SourceAliases::SourceId)
+                            .to(Sources::Table, Sources::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_source_aliases_source_id")
+                    .table(SourceAliases::Table)
+                    .col(SourceAliases::SourceId)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_source_aliases_source_id")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(SourceAliases::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SourceAliases {
+    Table,
+    Id,
+    Alias,
+    SourceId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Sources {
+    Table,
+    Id,
+}
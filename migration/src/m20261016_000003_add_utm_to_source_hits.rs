@@ -0,0 +1,72 @@
+use sea_orm_migration::{prelude::*, schema::string_null};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // UTM parameters are stored per hit so campaigns can be grouped later
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SourceHits::Table)
+                    .add_column(string_null(SourceHits::UtmSource))
+                    .add_column(string_null(SourceHits::UtmMedium))
+                    .add_column(string_null(SourceHits::UtmCampaign))
+                    .add_column(string_null(SourceHits::UtmTerm))
+                    .add_column(string_null(SourceHits::UtmContent))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_source_hits_utm_campaign_medium")
+                    .table(SourceHits::Table)
+                    .col(SourceHits::UtmCampaign)
+                    .col(SourceHits::UtmMedium)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_source_hits_utm_campaign_medium")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SourceHits::Table)
+                    .drop_column(SourceHits::UtmSource)
+                    .drop_column(SourceHits::UtmMedium)
+                    .drop_column(SourceHits::UtmCampaign)
+                    .drop_column(SourceHits::UtmTerm)
+                    .drop_column(SourceHits::UtmContent)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SourceHits {
+    Table,
+    UtmSource,
+    UtmMedium,
+    UtmCampaign,
+    UtmTerm,
+    UtmContent,
+}
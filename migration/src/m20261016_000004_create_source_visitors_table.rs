@@ -0,0 +1,63 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{date, integer, string_len},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // One row per visitor hash per source per day, the salt behind the hash
+        // rotates daily and is never stored
+        manager
+            .create_table(
+                Table::create()
+                    .table(SourceVisitors::Table)
+                    .if_not_exists()
+                    .col(integer(SourceVisitors::SourceId).not_null())
+                    .col(date(SourceVisitors::Day).not_null())
+                    .col(string_len(SourceVisitors::VisitorHash, 64).not_null())
+                    .primary_key(
+                        Index::create()
+                            .col(SourceVisitors::SourceId)
+                            .col(SourceVisitors::Day)
+                            .col(SourceVisitors::VisitorHash),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_source_visitors_source_id")
+                            .from(SourceVisitors::Table, SourceVisitors::SourceId)
+                            .to(Sources::Table, Sources::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(SourceVisitors::Table).to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum SourceVisitors {
+    Table,
+    SourceId,
+    Day,
+    VisitorHash,
+}
+
+#[derive(DeriveIden)]
+enum Sources {
+    Table,
+    Id,
+}
@@ -0,0 +1,98 @@
+use sea_orm_migration::{prelude::*, schema::boolean};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Bot hits are counted apart so they do not inflate the main counters
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sources::Table)
+                    .add_column(
+                        ColumnDef::new(Sources::BotCount)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BrewDownloads::Table)
+                    .add_column(
+                        ColumnDef::new(BrewDownloads::BotCount)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SourceHits::Table)
+                    .add_column(boolean(SourceHits::IsBot).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SourceHits::Table)
+                    .drop_column(SourceHits::IsBot)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(BrewDownloads::Table)
+                    .drop_column(BrewDownloads::BotCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sources::Table)
+                    .drop_column(Sources::BotCount)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Sources {
+    Table,
+    BotCount,
+}
+
+#[derive(DeriveIden)]
+enum BrewDownloads {
+    Table,
+    BotCount,
+}
+
+#[derive(DeriveIden)]
+enum SourceHits {
+    Table,
+    IsBot,
+}
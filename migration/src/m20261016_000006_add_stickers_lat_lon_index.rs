@@ -0,0 +1,39 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Bounding-box prefilter of the nearby search
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_stickers_lat_lon")
+                    .table(Stickers::Table)
+                    .col(Stickers::Latitude)
+                    .col(Stickers::Longitude)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_stickers_lat_lon").to_owned())
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Stickers {
+    Table,
+    Latitude,
+    Longitude,
+}
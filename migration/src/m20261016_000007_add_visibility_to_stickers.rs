@@ -0,0 +1,55 @@
+use sea_orm_migration::{prelude::*, schema::string_len};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Stickers stay private until explicitly published
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stickers::Table)
+                    .add_column(string_len(Stickers::Visibility, 16).default("private"))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_stickers_visibility")
+                    .table(Stickers::Table)
+                    .col(Stickers::Visibility)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(Index::drop().name("idx_stickers_visibility").to_owned())
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stickers::Table)
+                    .drop_column(Stickers::Visibility)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Stickers {
+    Table,
+    Visibility,
+}
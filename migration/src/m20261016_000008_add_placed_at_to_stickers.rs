@@ -0,0 +1,40 @@
+use sea_orm_migration::{prelude::*, schema::timestamp_null};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // When the sticker was placed, unknown for existing stickers
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stickers::Table)
+                    .add_column(timestamp_null(Stickers::PlacedAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stickers::Table)
+                    .drop_column(Stickers::PlacedAt)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Stickers {
+    Table,
+    PlacedAt,
+}
@@ -0,0 +1,46 @@
+use sea_orm_migration::{
+    prelude::*,
+    schema::{string_len_null, string_null},
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // ISO 3166-1 alpha-2 country and first-level region of the sticker
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stickers::Table)
+                    .add_column(string_len_null(Stickers::CountryCode, 2))
+                    .add_column(string_null(Stickers::Region))
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Stickers::Table)
+                    .drop_column(Stickers::CountryCode)
+                    .drop_column(Stickers::Region)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Stickers {
+    Table,
+    CountryCode,
+    Region,
+}
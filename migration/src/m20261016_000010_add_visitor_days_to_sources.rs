@@ -0,0 +1,61 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Visitor hashes are only needed to deduplicate a day, the running
+        // total lives on the source so old days can be pruned
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sources::Table)
+                    .add_column(
+                        ColumnDef::new(Sources::VisitorDays)
+                            .big_integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(
+                r"
+                UPDATE sources SET visitor_days = visitors.total
+                FROM (
+                    SELECT source_id, COUNT(*) AS total
+                    FROM source_visitors
+                    GROUP BY source_id
+                ) AS visitors
+                WHERE sources.id = visitors.source_id
+                ",
+            )
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Sources::Table)
+                    .drop_column(Sources::VisitorDays)
+                    .to_owned(),
+            )
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Sources {
+    Table,
+    VisitorDays,
+}
@@ -0,0 +1,185 @@
+//! Bot and crawler detection
+//!
+//! Requests are classified as bots when their `User-Agent` contains one of a
+//! list of patterns, or when the client IP falls in a configured range. The
+//! pattern list is bundled with the binary and can be replaced at startup
+//! with `BOT_PATTERNS_FILE`, without rebuilding.
+
+use anyhow::{Context, Result};
+use axum::http::{HeaderMap, header};
+use ipnet::IpNet;
+use serde::Deserialize;
+use std::{net::SocketAddr, sync::Arc};
+
+use crate::client::client_ip;
+
+/// Patterns bundled with the binary
+const BUNDLED_PATTERNS: &str = include_str!("patterns.txt");
+
+/// Query parameter toggling bot hits in statistics
+#[derive(Debug, Default, Deserialize)]
+pub struct IncludeBotsParams {
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+/// Classifies requests as human or bot.
+#[derive(Debug, Clone, Default)]
+pub struct BotFilter {
+    patterns: Arc<Vec<String>>,
+    ip_ranges: Arc<Vec<IpNet>>,
+    trust_proxy_headers: bool,
+}
+
+impl BotFilter {
+    /// Build the filter from the pattern list and IP ranges.
+    ///
+    /// # Arguments
+    /// * `patterns_file` - Pattern file replacing the bundled list, if set.
+    /// * `ip_ranges` - CIDR ranges whose clients are always bots.
+    /// * `trust_proxy_headers` - Read the client IP from proxy headers.
+    ///
+    /// # Errors
+    /// Returns an error if the pattern file cannot be read or a range is invalid.
+    pub fn new(
+        patterns_file: Option<&str>,
+        ip_ranges: &[String],
+        trust_proxy_headers: bool,
+    ) -> Result<Self> {
+        let patterns = match patterns_file {
+            Some(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read bot patterns from {path}"))?,
+            None => BUNDLED_PATTERNS.to_string(),
+        };
+
+        let ip_ranges = ip_ranges
+            .iter()
+            .map(|range| {
+                range
+                    .parse::<IpNet>()
+                    .with_context(|| format!("Invalid bot IP range: {range}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            patterns: Arc::new(parse_patterns(&patterns)),
+            ip_ranges: Arc::new(ip_ranges),
+            trust_proxy_headers,
+        })
+    }
+
+    /// Whether the request comes from a bot.
+    #[must_use]
+    pub fn is_bot(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> bool {
+        let user_agent = headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+
+        if self
+            .patterns
+            .iter()
+            .any(|pattern| user_agent.contains(pattern.as_str()))
+        {
+            return true;
+        }
+
+        client_ip(headers, peer, self.trust_proxy_headers)
+            .is_some_and(|ip| self.ip_ranges.iter().any(|range| range.contains(&ip)))
+    }
+}
+
+/// Parse a pattern list: one lowercase substring per line, `#` comments ignored.
+fn parse_patterns(contents: &str) -> Vec<String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn user_agent(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn peer(ip: &str) -> Option<SocketAddr> {
+        Some(SocketAddr::new(ip.parse().unwrap(), 443))
+    }
+
+    #[test]
+    fn patterns_skip_comments_and_blank_lines() {
+        let patterns =
+            parse_patterns("# Crawlers\n\nGoogleBot\n  Spider  \n   # indented comment\n\t\n");
+
+        assert_eq!(patterns, ["googlebot", "spider"]);
+    }
+
+    #[test]
+    fn patterns_file_replaces_the_bundled_list() {
+        let path = std::env::temp_dir().join(format!("bot-patterns-{}.txt", std::process::id()));
+        std::fs::write(&path, "# Only this one\nMyCrawler\n").unwrap();
+
+        let filter = BotFilter::new(path.to_str(), &[], false).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(filter.is_bot(&user_agent("Mozilla/5.0 (compatible; mycrawler/1.0)"), None));
+        assert!(!filter.is_bot(&user_agent("Googlebot/2.1"), None));
+        assert!(BotFilter::new(Some("/nonexistent/patterns.txt"), &[], false).is_err());
+    }
+
+    #[test]
+    fn user_agents_are_matched_without_case() {
+        let filter = BotFilter::new(None, &[], false).unwrap();
+
+        assert!(filter.is_bot(
+            &user_agent("Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)"),
+            None
+        ));
+        assert!(filter.is_bot(&user_agent("facebookexternalhit/1.1"), None));
+        assert!(!filter.is_bot(
+            &user_agent("Mozilla/5.0 (X11; Linux x86_64; rv:131.0) Gecko/20100101 Firefox/131.0"),
+            None
+        ));
+        assert!(!filter.is_bot(&HeaderMap::new(), None));
+    }
+
+    #[test]
+    fn clients_in_ip_ranges_are_bots() {
+        let ranges = [
+            "66.249.64.0/19".to_string(),
+            "2001:4860:4801::/48".to_string(),
+        ];
+        let filter = BotFilter::new(None, &ranges, false).unwrap();
+        let browser = user_agent("Mozilla/5.0");
+
+        assert!(filter.is_bot(&browser, peer("66.249.66.1")));
+        assert!(filter.is_bot(&browser, peer("66.249.95.255")));
+        assert!(!filter.is_bot(&browser, peer("66.249.96.0")));
+        assert!(filter.is_bot(&browser, peer("2001:4860:4801:10::1")));
+        assert!(!filter.is_bot(&browser, peer("2001:4860:4802::1")));
+        assert!(!filter.is_bot(&browser, None));
+        assert!(BotFilter::new(None, &["66.249.64.0/33".to_string()], false).is_err());
+    }
+
+    #[test]
+    fn forwarded_ip_is_only_used_when_trusted() {
+        let ranges = ["66.249.64.0/19".to_string()];
+        let mut headers = user_agent("Mozilla/5.0");
+        headers.insert("x-forwarded-for", HeaderValue::from_static("66.249.66.1"));
+
+        let untrusted = BotFilter::new(None, &ranges, false).unwrap();
+        assert!(!untrusted.is_bot(&headers, peer("10.0.0.1")));
+
+        let trusted = BotFilter::new(None, &ranges, true).unwrap();
+        assert!(trusted.is_bot(&headers, peer("10.0.0.1")));
+    }
+}
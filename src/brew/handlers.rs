@@ -1,18 +1,20 @@
 use anyhow::Context;
 use axum::{
-    Json,
-    extract::{Path, State},
-    http::{StatusCode, header},
+    Extension, Json,
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
     response::{IntoResponse, Response},
 };
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set};
+use sea_orm::{DatabaseConnection, EntityTrait};
 use serde_json::json;
-use std::collections::HashMap;
+use std::{collections::HashMap, net::SocketAddr};
 use tracing::info;
 
 use crate::{
+    bot::{BotFilter, IncludeBotsParams},
+    counter::Counters,
     data_response,
-    entities::{brew_downloads, prelude::*},
+    entities::prelude::*,
     error::{ApiError, ApiResult},
 };
 
@@ -47,6 +49,8 @@ fn parse_brew_filename(project: &str, filename: &str) -> Option<(String, String)
 /// which hits this endpoint. The server records the download and issues a 302 redirect
 /// to the actual GitHub release asset.
 ///
+/// Downloads from bots and crawlers are counted separately.
+///
 /// # Path parameters
 /// * `project` - The formula/project name (e.g. `rona`, `clean-dev-dirs`)
 /// * `filename` - The bottle filename (e.g. `rona-2.17.7.arm64_sequoia.bottle.tar.gz`)
@@ -59,7 +63,10 @@ fn parse_brew_filename(project: &str, filename: &str) -> Option<(String, String)
 /// * 400 if the filename cannot be parsed
 /// * 500 on database or header-value errors
 pub async fn track_brew_download(
-    State(db): State<DatabaseConnection>,
+    Extension(counters): Extension<Counters>,
+    Extension(bots): Extension<BotFilter>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
     Path((project, filename)): Path<(String, String)>,
 ) -> ApiResult<Response> {
     info!("GET `/brew/track/{project}/{filename}` endpoint called");
@@ -70,35 +77,12 @@ pub async fn track_brew_download(
     let (version, platform) = parse_brew_filename(&project, &filename)
         .ok_or_else(|| ApiError::validation(format!("Could not parse filename: {filename}")))?;
 
-    // Upsert: increment count if row exists, insert with count=1 otherwise.
-    let existing = BrewDownloads::find()
-        .filter(brew_downloads::Column::Project.eq(&project))
-        .filter(brew_downloads::Column::Version.eq(&version))
-        .filter(brew_downloads::Column::Platform.eq(&platform))
-        .one(&db)
+    let bot = bots.is_bot(&headers, connect_info.map(|ConnectInfo(addr)| addr));
+
+    counters
+        .increment_brew_download(&project, &version, &platform, 1, bot)
         .await
-        .context("Failed to query brew download record")?;
-
-    if let Some(model) = existing {
-        let mut active: brew_downloads::ActiveModel = model.into();
-        active.count = Set(active.count.unwrap() + 1);
-        active
-            .update(&db)
-            .await
-            .context("Failed to update brew download count")?;
-    } else {
-        let new_record = brew_downloads::ActiveModel {
-            project: Set(project.clone()),
-            version: Set(version.clone()),
-            platform: Set(platform),
-            count: Set(1),
-            ..Default::default()
-        };
-        new_record
-            .insert(&db)
-            .await
-            .context("Failed to insert brew download record")?;
-    }
+        .context("Failed to record brew download")?;
 
     let redirect_url =
         format!("https://github.com/{org}/{repo}/releases/download/v{version}/{filename}");
@@ -121,10 +105,13 @@ struct ProjectStats {
 /// JSON object keyed by project name, containing `total_downloads`, `total_installs`,
 /// and per-version `downloads`/`installs` counts.
 ///
+/// Bot downloads are left out unless `include_bots=true`.
+///
 /// # Errors
 /// Returns 500 on database failure.
 pub async fn get_brew_stats(
     State(db): State<DatabaseConnection>,
+    Query(params): Query<IncludeBotsParams>,
 ) -> ApiResult<Json<serde_json::Value>> {
     info!("GET `/brew/stats` endpoint called");
 
@@ -136,11 +123,17 @@ pub async fn get_brew_stats(
     let mut stats: HashMap<String, ProjectStats> = HashMap::new();
 
     for row in rows {
-        let entry = stats.entry(row.project.clone()).or_insert_with(|| ProjectStats {
-            total: 0,
-            versions: HashMap::new(),
-        });
-        let count = i64::from(row.count);
+        let entry = stats
+            .entry(row.project.clone())
+            .or_insert_with(|| ProjectStats {
+                total: 0,
+                versions: HashMap::new(),
+            });
+        let count = if params.include_bots {
+            row.count + row.bot_count
+        } else {
+            row.count
+        };
         entry.total += count;
         *entry.versions.entry(row.version.clone()).or_insert(0) += count;
     }
@@ -0,0 +1,90 @@
+//! Client identification helpers
+
+use axum::http::HeaderMap;
+use std::net::{IpAddr, SocketAddr};
+
+/// Resolve the client IP of a request.
+///
+/// # Arguments
+/// * `headers` - The request headers.
+/// * `peer` - The socket address of the connection, when available.
+/// * `trust_proxy_headers` - Read `X-Forwarded-For` / `X-Real-IP` first, only safe
+///   behind a reverse proxy that sets them.
+#[must_use]
+pub fn client_ip(
+    headers: &HeaderMap,
+    peer: Option<SocketAddr>,
+    trust_proxy_headers: bool,
+) -> Option<IpAddr> {
+    if trust_proxy_headers {
+        let forwarded = headers
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.split(',').next())
+            .or_else(|| {
+                headers
+                    .get("x-real-ip")
+                    .and_then(|value| value.to_str().ok())
+            })
+            .and_then(|value| value.trim().parse().ok());
+
+        if forwarded.is_some() {
+            return forwarded;
+        }
+    }
+
+    peer.map(|addr| addr.ip())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    fn peer() -> Option<SocketAddr> {
+        Some("10.0.0.1:443".parse().unwrap())
+    }
+
+    fn ip(value: &str) -> Option<IpAddr> {
+        Some(value.parse().unwrap())
+    }
+
+    #[test]
+    fn proxy_headers_are_ignored_unless_trusted() {
+        let headers = headers(&[
+            ("x-forwarded-for", "203.0.113.7"),
+            ("x-real-ip", "203.0.113.8"),
+        ]);
+
+        assert_eq!(client_ip(&headers, peer(), false), ip("10.0.0.1"));
+        assert_eq!(client_ip(&headers, None, false), None);
+    }
+
+    #[test]
+    fn first_forwarded_address_wins_when_trusted() {
+        let forwarded = headers(&[
+            ("x-forwarded-for", " 203.0.113.7 , 198.51.100.2"),
+            ("x-real-ip", "203.0.113.8"),
+        ]);
+        assert_eq!(client_ip(&forwarded, peer(), true), ip("203.0.113.7"));
+
+        let real_ip = headers(&[("x-real-ip", "2001:db8::1")]);
+        assert_eq!(client_ip(&real_ip, peer(), true), ip("2001:db8::1"));
+    }
+
+    #[test]
+    fn unparsable_proxy_headers_fall_back_to_the_peer() {
+        let garbage = headers(&[("x-forwarded-for", "unknown")]);
+        assert_eq!(client_ip(&garbage, peer(), true), ip("10.0.0.1"));
+
+        assert_eq!(client_ip(&HeaderMap::new(), peer(), true), ip("10.0.0.1"));
+    }
+}
@@ -1,5 +1,24 @@
+use serde::Serialize;
 use std::env;
 
+use crate::source::{
+    normalize::SourceNormalization,
+    referrer::ReferrerSources,
+    validate::{self, SourceValidation, UnknownSources},
+};
+use crate::sticker::{export::PublicBaseUrl, fuzz::CoordinateFuzzing};
+
+/// How counter increments are written to the database.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CounterMode {
+    /// Every increment is written inside the request
+    #[serde(rename = "sync")]
+    Synchronous,
+    /// Increments are aggregated in memory and flushed periodically
+    Buffered,
+}
+
 /// Application configuration loaded from environment variables.
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -10,6 +29,21 @@ pub struct Config {
     pub rust_log: String,
     pub static_dir: String,
     pub github_token: Option<String>,
+    pub counter_mode: CounterMode,
+    pub counter_flush_interval_secs: u64,
+    pub counter_flush_threshold: i64,
+    pub source_normalization: SourceNormalization,
+    pub source_validation: SourceValidation,
+    pub referrer_sources: ReferrerSources,
+    pub track_redirect_hosts: Vec<String>,
+    pub trust_proxy_headers: bool,
+    pub bot_patterns_file: Option<String>,
+    pub bot_ip_ranges: Vec<String>,
+    pub sticker_public_fuzzing: CoordinateFuzzing,
+    pub sticker_picture_max_bytes: usize,
+    pub geocoder_cities_file: Option<String>,
+    pub geocoder_regions_file: Option<String>,
+    pub public_base_url: PublicBaseUrl,
 }
 
 impl Config {
@@ -43,6 +77,84 @@ impl Config {
 
         let github_token = env::var("GITHUB_TOKEN").ok();
 
+        let counter_mode = match env::var("COUNTER_MODE")
+            .unwrap_or_else(|_| "sync".to_string())
+            .as_str()
+        {
+            "sync" => CounterMode::Synchronous,
+            "buffered" => CounterMode::Buffered,
+            _ => return Err("COUNTER_MODE must be either `sync` or `buffered`".to_string()),
+        };
+
+        let counter_flush_interval_secs = env::var("COUNTER_FLUSH_INTERVAL_SECS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse::<u64>()
+            .ok()
+            .filter(|secs| *secs > 0)
+            .ok_or("COUNTER_FLUSH_INTERVAL_SECS must be a positive number")?;
+
+        let counter_flush_threshold = env::var("COUNTER_FLUSH_THRESHOLD")
+            .unwrap_or_else(|_| "100".to_string())
+            .parse::<i64>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or("COUNTER_FLUSH_THRESHOLD must be a positive number")?;
+
+        let source_normalization = SourceNormalization::from_spec(
+            &env::var("SOURCE_NORMALIZATION").unwrap_or_else(|_| "trim".to_string()),
+        )?;
+
+        let source_validation = source_validation_from_env(source_normalization)?;
+
+        let referrer_sources =
+            ReferrerSources::from_spec(&env::var("REFERRER_SOURCES").unwrap_or_default())?;
+
+        let track_redirect_hosts = env::var("TRACK_REDIRECT_HOSTS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let trust_proxy_headers = env::var("TRUST_PROXY_HEADERS")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse::<bool>()
+            .map_err(|_| "TRUST_PROXY_HEADERS must be `true` or `false`")?;
+
+        let bot_patterns_file = env::var("BOT_PATTERNS_FILE").ok();
+
+        let bot_ip_ranges = env::var("BOT_IP_RANGES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let sticker_public_fuzzing = CoordinateFuzzing::from_spec(
+            &env::var("STICKER_PUBLIC_FUZZING").unwrap_or_else(|_| "round:2".to_string()),
+            env::var("STICKER_FUZZING_SALT").ok().as_deref(),
+        )?;
+
+        let sticker_picture_max_bytes = env::var("STICKER_PICTURE_MAX_BYTES")
+            .unwrap_or_else(|_| "10485760".to_string())
+            .parse::<usize>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or("STICKER_PICTURE_MAX_BYTES must be a positive number")?;
+
+        let geocoder_cities_file = env::var("GEOCODER_CITIES_FILE").ok();
+
+        let geocoder_regions_file = env::var("GEOCODER_REGIONS_FILE").ok();
+
+        // IPv6 hosts need brackets in the default URL
+        let public_base_url =
+            PublicBaseUrl::from_spec(&env::var("PUBLIC_BASE_URL").unwrap_or_else(|_| {
+                match host.parse::<std::net::Ipv6Addr>() {
+                    Ok(ip) => format!("http://[{ip}]:{port}"),
+                    Err(_) => format!("http://{host}:{port}"),
+                }
+            }))?;
+
         Ok(Self {
             host,
             port,
@@ -51,6 +163,72 @@ impl Config {
             rust_log,
             static_dir,
             github_token,
+            counter_mode,
+            counter_flush_interval_secs,
+            counter_flush_threshold,
+            source_normalization,
+            source_validation,
+            referrer_sources,
+            track_redirect_hosts,
+            trust_proxy_headers,
+            bot_patterns_file,
+            bot_ip_ranges,
+            sticker_public_fuzzing,
+            sticker_picture_max_bytes,
+            geocoder_cities_file,
+            geocoder_regions_file,
+            public_base_url,
         })
     }
 }
+
+/// Load the source name rules from the `SOURCE_*` variables.
+///
+/// Allow-listed names go through `normalization` so they compare equal to
+/// normalized hits.
+fn source_validation_from_env(
+    normalization: SourceNormalization,
+) -> Result<SourceValidation, String> {
+    let defaults = SourceValidation::default();
+
+    let max_length = env::var("SOURCE_MAX_LENGTH")
+        .map_or(Ok(defaults.max_length), |value| value.parse::<usize>())
+        .ok()
+        .filter(|n| *n > 0)
+        .ok_or("SOURCE_MAX_LENGTH must be a positive number")?;
+
+    let pattern = env::var("SOURCE_PATTERN")
+        .ok()
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            validate::whole_name_pattern(&value)
+                .map_err(|e| format!("SOURCE_PATTERN is not a valid regex: {e}"))
+        })
+        .transpose()?;
+
+    let allowlist = env::var("SOURCE_ALLOWLIST").ok().map(|value| {
+        std::sync::Arc::new(
+            value
+                .split(',')
+                .map(|name| normalization.apply(name))
+                .filter(|name| !name.is_empty())
+                .collect(),
+        )
+    });
+
+    let unknown = match env::var("SOURCE_UNKNOWN")
+        .unwrap_or_else(|_| "create".to_string())
+        .as_str()
+    {
+        "create" => UnknownSources::Create,
+        "reject" => UnknownSources::Reject,
+        _ => return Err("SOURCE_UNKNOWN must be either `create` or `reject`".to_string()),
+    };
+
+    Ok(SourceValidation {
+        max_length,
+        pattern,
+        allowlist,
+        unknown,
+    })
+}
@@ -0,0 +1,496 @@
+//! Write-behind counter buffer
+//!
+//! Increments are aggregated in memory, keyed by the unique columns of their
+//! table, and written in a single transaction when the flush interval elapses,
+//! when the number of buffered events reaches the threshold, or on shutdown.
+//!
+//! Source hits are also keyed by the minute they were received, so hit events
+//! keep their time instead of taking the time of the flush.
+//!
+//! When the flush transaction fails, every key is retried on its own so one
+//! failing key cannot hold back the others. Keys that still fail are kept for
+//! the next flush and dropped after [`MAX_FAILURES`] attempts, and the flush
+//! task backs off while flushes keep failing.
+
+use anyhow::{Context, Result};
+use chrono::{DurationRound, NaiveDateTime, TimeDelta};
+use sea_orm::{DatabaseConnection, TransactionTrait};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+use tokio::sync::Notify;
+
+use super::HitDetails;
+use crate::source::{utm::Utm, visitor::Visitor};
+
+/// Failed flushes after which a key is dropped
+pub const MAX_FAILURES: u32 = 5;
+
+/// Longest wait between flushes while they keep failing
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+/// Source name, UTM parameters, bot flag and minute of its hits
+type SourceKey = (String, Utm, bool, NaiveDateTime);
+
+/// Unique key of a `brew_downloads` row, `(project, version, platform)`, and the bot flag
+type BrewKey = (String, String, String, bool);
+
+/// A buffered increment and the number of flushes it failed
+#[derive(Debug, Clone, Copy, Default)]
+struct Increment {
+    by: i64,
+    failures: u32,
+}
+
+/// Increments not yet written to the database
+#[derive(Debug, Default)]
+struct Pending {
+    sources: BTreeMap<SourceKey, Increment>,
+    visitors: BTreeMap<(String, Visitor), u32>,
+    brew_downloads: BTreeMap<BrewKey, Increment>,
+    increments: i64,
+}
+
+impl Pending {
+    fn is_empty(&self) -> bool {
+        self.sources.is_empty() && self.visitors.is_empty() && self.brew_downloads.is_empty()
+    }
+
+    fn len(&self) -> usize {
+        self.sources.len() + self.visitors.len() + self.brew_downloads.len()
+    }
+}
+
+/// Snapshot of the buffer contents
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct PendingStats {
+    /// Total increments waiting to be flushed
+    pub increments: i64,
+    /// Distinct source, UTM, bot and minute combinations with pending increments
+    pub sources: usize,
+    /// Distinct brew bottles with pending increments
+    pub brew_downloads: usize,
+}
+
+/// In-process aggregation buffer for counter increments.
+#[derive(Debug)]
+pub struct CounterBuffer {
+    db: DatabaseConnection,
+    pending: Mutex<Pending>,
+    flush_requested: Notify,
+    threshold: i64,
+}
+
+impl CounterBuffer {
+    /// Create the buffer and spawn its periodic flush task.
+    ///
+    /// # Arguments
+    /// * `db` - The database connection used for flushes.
+    /// * `interval` - Maximum time increments stay buffered.
+    /// * `threshold` - Number of buffered increments that triggers an early flush.
+    #[must_use]
+    pub fn spawn(db: DatabaseConnection, interval: Duration, threshold: i64) -> Arc<Self> {
+        let buffer = Arc::new(Self {
+            db,
+            pending: Mutex::new(Pending::default()),
+            flush_requested: Notify::new(),
+            threshold,
+        });
+
+        let worker = Arc::clone(&buffer);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut backoff = interval;
+
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {}
+                    () = worker.flush_requested.notified() => {}
+                }
+
+                match worker.flush().await {
+                    Ok(()) => backoff = interval,
+                    Err(e) => {
+                        tracing::error!(
+                            "Counter buffer flush failed, retrying in {}s: {e:#}",
+                            backoff.as_secs()
+                        );
+                        tokio::time::sleep(backoff).await;
+                        backoff = next_backoff(backoff, interval);
+                    }
+                }
+            }
+        });
+
+        buffer
+    }
+
+    /// Buffer an increment of a source counter and its visitor.
+    pub fn add_source(&self, name: &str, by: i64, hit: &HitDetails) {
+        let at = hit
+            .at
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc())
+            .duration_trunc(TimeDelta::minutes(1))
+            .unwrap_or_default();
+
+        let mut pending = self.lock();
+        let key = (name.to_string(), hit.utm.clone(), hit.bot, at);
+        pending.sources.entry(key).or_default().by += by;
+        if let Some(visitor) = hit.visitor.as_ref().filter(|_| !hit.bot) {
+            pending
+                .visitors
+                .entry((name.to_string(), visitor.clone()))
+                .or_insert(0);
+        }
+        self.record(&mut pending, by);
+    }
+
+    /// Buffer an increment of a brew download counter.
+    pub fn add_brew_download(
+        &self,
+        project: &str,
+        version: &str,
+        platform: &str,
+        by: i64,
+        bot: bool,
+    ) {
+        let mut pending = self.lock();
+        let key = (
+            project.to_string(),
+            version.to_string(),
+            platform.to_string(),
+            bot,
+        );
+        pending.brew_downloads.entry(key).or_default().by += by;
+        self.record(&mut pending, by);
+    }
+
+    /// Report what is currently waiting to be flushed.
+    #[must_use]
+    pub fn pending(&self) -> PendingStats {
+        let pending = self.lock();
+        PendingStats {
+            increments: pending.increments,
+            sources: pending.sources.len(),
+            brew_downloads: pending.brew_downloads.len(),
+        }
+    }
+
+    /// Write all buffered increments.
+    ///
+    /// The batch is written in a single transaction. If it fails, each key is
+    /// written on its own and the keys that still fail are put back into the
+    /// buffer for the next flush.
+    ///
+    /// # Errors
+    /// Returns an error if some keys could not be written.
+    pub async fn flush(&self) -> Result<()> {
+        let batch = std::mem::take(&mut *self.lock());
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let error = match self.write(&batch).await {
+            Ok(()) => {
+                tracing::debug!("Flushed {} buffered counter increments", batch.increments);
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        tracing::warn!("Counter flush transaction failed, writing keys one by one: {error:#}");
+
+        let keys = batch.len();
+        let failed = self.write_each(batch).await;
+        let failures = failed.len();
+        self.restore(failed);
+
+        if failures == 0 {
+            Ok(())
+        } else {
+            Err(error.context(format!(
+                "{failures} of {keys} buffered counter keys failed to flush"
+            )))
+        }
+    }
+
+    /// Apply a batch of increments, in key order to keep lock order stable.
+    async fn write(&self, batch: &Pending) -> Result<()> {
+        let txn = self
+            .db
+            .begin()
+            .await
+            .context("Failed to start counter flush transaction")?;
+
+        for ((name, utm, bot, at), increment) in &batch.sources {
+            let hit = source_hit(utm, *bot, *at);
+            super::increment_source(&txn, name, increment.by, &hit).await?;
+        }
+
+        for (name, visitor) in batch.visitors.keys() {
+            super::record_visitor(&txn, name, visitor).await?;
+        }
+
+        for ((project, version, platform, bot), increment) in &batch.brew_downloads {
+            super::increment_brew_download(&txn, project, version, platform, increment.by, *bot)
+                .await?;
+        }
+
+        txn.commit()
+            .await
+            .context("Failed to commit counter flush transaction")
+    }
+
+    /// Apply each increment of a batch on its own.
+    ///
+    /// # Returns
+    /// The increments that failed, with their failure counts settled by
+    /// [`settle_failures`].
+    async fn write_each(&self, batch: Pending) -> Pending {
+        let keys = batch.len();
+        let mut failed = Pending::default();
+
+        for (key, increment) in batch.sources {
+            let (name, utm, bot, at) = &key;
+            let hit = source_hit(utm, *bot, *at);
+            if let Err(e) = super::increment_source(&self.db, name, increment.by, &hit).await {
+                tracing::warn!("Failed to flush source '{name}': {e:#}");
+                failed.increments += increment.by;
+                failed.sources.insert(key, increment);
+            }
+        }
+
+        for (key, failures) in batch.visitors {
+            let (name, visitor) = &key;
+            if let Err(e) = super::record_visitor(&self.db, name, visitor).await {
+                tracing::warn!("Failed to flush visitor of source '{name}': {e:#}");
+                failed.visitors.insert(key, failures);
+            }
+        }
+
+        for (key, increment) in batch.brew_downloads {
+            let (project, version, platform, bot) = &key;
+            let result = super::increment_brew_download(
+                &self.db,
+                project,
+                version,
+                platform,
+                increment.by,
+                *bot,
+            )
+            .await;
+            if let Err(e) = result {
+                tracing::warn!("Failed to flush brew download {project} {version}: {e:#}");
+                failed.increments += increment.by;
+                failed.brew_downloads.insert(key, increment);
+            }
+        }
+
+        settle_failures(&mut failed, keys);
+        failed
+    }
+
+    /// Merge a batch that failed to flush back into the buffer.
+    fn restore(&self, batch: Pending) {
+        merge_failed(&mut self.lock(), batch);
+    }
+
+    /// Count a buffered increment and wake the flush task past the threshold.
+    fn record(&self, pending: &mut Pending, by: i64) {
+        pending.increments += by;
+        if pending.increments >= self.threshold {
+            self.flush_requested.notify_one();
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Pending> {
+        self.pending.lock().expect("counter buffer lock poisoned")
+    }
+}
+
+/// Wait before the flush following a failed one: twice the previous wait, up
+/// to [`MAX_BACKOFF`], or to the flush interval when it is longer.
+fn next_backoff(backoff: Duration, interval: Duration) -> Duration {
+    (backoff * 2).min(MAX_BACKOFF.max(interval))
+}
+
+/// Merge increments that failed to flush into the pending ones, keeping the
+/// highest failure count of each key.
+fn merge_failed(pending: &mut Pending, batch: Pending) {
+    for (key, failed) in batch.sources {
+        let increment = pending.sources.entry(key).or_default();
+        increment.by += failed.by;
+        increment.failures = increment.failures.max(failed.failures);
+    }
+    for (key, failures) in batch.visitors {
+        let current = pending.visitors.entry(key).or_insert(0);
+        *current = (*current).max(failures);
+    }
+    for (key, failed) in batch.brew_downloads {
+        let increment = pending.brew_downloads.entry(key).or_default();
+        increment.by += failed.by;
+        increment.failures = increment.failures.max(failed.failures);
+    }
+    pending.increments += batch.increments;
+}
+
+/// Details of a buffered source hit.
+fn source_hit(utm: &Utm, bot: bool, at: NaiveDateTime) -> HitDetails {
+    HitDetails {
+        utm: utm.clone(),
+        visitor: None,
+        bot,
+        at: Some(at),
+    }
+}
+
+/// Record a failed flush on the keys that failed to write on their own.
+///
+/// When every key of the batch failed the database is likely unreachable,
+/// so the failure counts are left unchanged.
+fn settle_failures(failed: &mut Pending, keys: usize) {
+    if failed.len() < keys {
+        count_failure(failed);
+    }
+}
+
+/// Record one more failed flush of a key.
+///
+/// # Returns
+/// Whether the key is kept, `false` once it failed [`MAX_FAILURES`] flushes.
+const fn record_failure(failures: &mut u32) -> bool {
+    *failures += 1;
+    *failures < MAX_FAILURES
+}
+
+/// Record a failed flush on every key of a batch, dropping the keys that
+/// failed too often.
+fn count_failure(batch: &mut Pending) {
+    batch.sources.retain(|(name, ..), increment| {
+        let keep = record_failure(&mut increment.failures);
+        if !keep {
+            tracing::error!(
+                "Dropping {} buffered hits of source '{name}' after {MAX_FAILURES} failed flushes",
+                increment.by
+            );
+            batch.increments -= increment.by;
+        }
+        keep
+    });
+
+    batch.visitors.retain(|(name, _), failures| {
+        let keep = record_failure(failures);
+        if !keep {
+            tracing::error!(
+                "Dropping a buffered visitor of source '{name}' after {MAX_FAILURES} failed flushes"
+            );
+        }
+        keep
+    });
+
+    batch
+        .brew_downloads
+        .retain(|(project, version, platform, _), increment| {
+            let keep = record_failure(&mut increment.failures);
+            if !keep {
+                tracing::error!(
+                    "Dropping {} buffered downloads of {project} {version} ({platform}) after {MAX_FAILURES} failed flushes",
+                    increment.by
+                );
+                batch.increments -= increment.by;
+            }
+            keep
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_key(name: &str) -> SourceKey {
+        (
+            name.to_string(),
+            Utm::default(),
+            false,
+            NaiveDateTime::default(),
+        )
+    }
+
+    fn failed_source(name: &str, by: i64, failures: u32) -> Pending {
+        let mut batch = Pending::default();
+        batch
+            .sources
+            .insert(source_key(name), Increment { by, failures });
+        batch.increments = by;
+        batch
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let interval = Duration::from_secs(10);
+
+        let mut backoff = interval;
+        let mut waits = Vec::new();
+        for _ in 0..7 {
+            backoff = next_backoff(backoff, interval);
+            waits.push(backoff.as_secs());
+        }
+
+        assert_eq!(waits, [20, 40, 80, 160, 300, 300, 300]);
+    }
+
+    #[test]
+    fn backoff_never_drops_below_the_interval() {
+        let interval = Duration::from_secs(600);
+
+        assert_eq!(next_backoff(interval, interval), interval);
+    }
+
+    #[test]
+    fn key_is_dropped_after_max_failures() {
+        let mut failures = 0;
+        let kept: Vec<bool> = (0..MAX_FAILURES)
+            .map(|_| record_failure(&mut failures))
+            .collect();
+
+        assert_eq!(kept, [true, true, true, true, false]);
+    }
+
+    #[test]
+    fn dropped_keys_leave_the_pending_increments() {
+        let mut batch = failed_source("twitter", 3, MAX_FAILURES - 1);
+        merge_failed(&mut batch, failed_source("github", 2, 0));
+
+        count_failure(&mut batch);
+
+        assert!(!batch.sources.contains_key(&source_key("twitter")));
+        assert_eq!(batch.sources[&source_key("github")].failures, 1);
+        assert_eq!(batch.increments, 2);
+    }
+
+    #[test]
+    fn failures_are_not_counted_when_every_key_fails() {
+        let mut failed = failed_source("twitter", 1, 2);
+
+        settle_failures(&mut failed, 1);
+        assert_eq!(failed.sources[&source_key("twitter")].failures, 2);
+
+        settle_failures(&mut failed, 2);
+        assert_eq!(failed.sources[&source_key("twitter")].failures, 3);
+    }
+
+    #[test]
+    fn restored_keys_keep_new_increments_and_failure_count() {
+        let mut pending = failed_source("twitter", 4, 0);
+
+        merge_failed(&mut pending, failed_source("twitter", 3, 2));
+
+        let increment = pending.sources[&source_key("twitter")];
+        assert_eq!(increment.by, 7);
+        assert_eq!(increment.failures, 2);
+        assert_eq!(pending.increments, 7);
+    }
+}
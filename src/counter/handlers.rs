@@ -0,0 +1,19 @@
+use axum::{Extension, Json};
+use serde_json::{Value, json};
+use tracing::info;
+
+use super::Counters;
+use crate::data_response;
+
+/// Handles GET requests for the counter write mode and buffer state.
+///
+/// # Returns
+/// The configured mode (`sync` or `buffered`) and the increments waiting to be flushed.
+pub async fn get_counter_stats(Extension(counters): Extension<Counters>) -> Json<Value> {
+    info!("GET `/stats/counters` endpoint called");
+
+    data_response(json!({
+        "mode": counters.mode(),
+        "pending": counters.pending(),
+    }))
+}
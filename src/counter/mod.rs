@@ -0,0 +1,409 @@
+//! Atomic counter service
+//!
+//! This module provides single-round-trip counter increments for the `sources`
+//! and `brew_downloads` tables. Each increment is an
+//! `INSERT ... ON CONFLICT DO UPDATE ... RETURNING count` against the table's
+//! unique index, so concurrent hits never lose updates.
+//!
+//! Source increments resolve names through `source_aliases` and append an
+//! event to `source_hits` in the same statement, then record the hashed
+//! visitor in `source_visitors`. A visitor new for the day adds one to
+//! `sources.visitor_days`; visitor rows of past days are pruned since their
+//! hashes can never be seen again.
+//!
+//! Hits classified as bots go to the separate `bot_count` column instead of
+//! `count`, and bot visitors are not recorded.
+//!
+//! Handlers go through [`Counters`], which either applies increments
+//! immediately or hands them to the write-behind [`CounterBuffer`],
+//! depending on `COUNTER_MODE`.
+
+pub mod buffer;
+pub mod handlers;
+
+use anyhow::{Context, Result};
+use chrono::NaiveDateTime;
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbBackend, Statement};
+use std::{sync::Arc, time::Duration};
+
+use crate::{
+    config::{Config, CounterMode},
+    source::{utm::Utm, visitor::Visitor},
+};
+pub use buffer::{CounterBuffer, PendingStats};
+
+/// How often visitor rows of past days are pruned
+const VISITOR_PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Counter value of a source after an increment
+#[derive(Debug, Clone)]
+pub struct SourceCount {
+    /// Canonical source name, after alias resolution
+    pub name: String,
+    /// New value of the counter
+    pub count: i64,
+}
+
+/// Details recorded with a source hit
+#[derive(Debug, Clone, Default)]
+pub struct HitDetails {
+    /// UTM parameters stored on the hit event
+    pub utm: Utm,
+    /// Hashed visitor, counted once per source and day
+    pub visitor: Option<Visitor>,
+    /// Whether the hit comes from a bot
+    pub bot: bool,
+    /// When the hit happened (UTC), `None` for the current time
+    pub at: Option<NaiveDateTime>,
+}
+
+/// Counter handle shared by the request handlers.
+#[derive(Debug, Clone)]
+pub struct Counters {
+    db: DatabaseConnection,
+    buffer: Option<Arc<CounterBuffer>>,
+}
+
+impl Counters {
+    /// Create the counter handle for the configured mode.
+    ///
+    /// Spawns the visitor pruning task and, in buffered mode, the periodic
+    /// flush task.
+    #[must_use]
+    pub fn from_config(db: DatabaseConnection, config: &Config) -> Self {
+        spawn_visitor_pruning(db.clone());
+
+        let buffer = match config.counter_mode {
+            CounterMode::Synchronous => None,
+            CounterMode::Buffered => Some(CounterBuffer::spawn(
+                db.clone(),
+                Duration::from_secs(config.counter_flush_interval_secs),
+                config.counter_flush_threshold,
+            )),
+        };
+
+        Self { db, buffer }
+    }
+
+    /// The database connection increments are written to.
+    #[must_use]
+    pub const fn db(&self) -> &DatabaseConnection {
+        &self.db
+    }
+
+    /// The configured counter mode.
+    #[must_use]
+    pub const fn mode(&self) -> CounterMode {
+        if self.buffer.is_some() {
+            CounterMode::Buffered
+        } else {
+            CounterMode::Synchronous
+        }
+    }
+
+    /// Increment a source counter, recording the hit details.
+    ///
+    /// # Returns
+    /// The canonical name and new count in synchronous mode, `None` when the
+    /// increment was buffered.
+    ///
+    /// # Errors
+    /// Returns an error if the synchronous upsert fails.
+    pub async fn increment_source(
+        &self,
+        name: &str,
+        by: i64,
+        hit: &HitDetails,
+    ) -> Result<Option<SourceCount>> {
+        if let Some(buffer) = &self.buffer {
+            buffer.add_source(name, by, hit);
+            return Ok(None);
+        }
+
+        increment_source(&self.db, name, by, hit).await.map(Some)
+    }
+
+    /// Increment a brew download counter, or its bot counter.
+    ///
+    /// # Returns
+    /// The new count in synchronous mode, `None` when the increment was buffered.
+    ///
+    /// # Errors
+    /// Returns an error if the synchronous upsert fails.
+    pub async fn increment_brew_download(
+        &self,
+        project: &str,
+        version: &str,
+        platform: &str,
+        by: i64,
+        bot: bool,
+    ) -> Result<Option<i64>> {
+        if let Some(buffer) = &self.buffer {
+            buffer.add_brew_download(project, version, platform, by, bot);
+            return Ok(None);
+        }
+
+        increment_brew_download(&self.db, project, version, platform, by, bot)
+            .await
+            .map(Some)
+    }
+
+    /// Increments waiting to be flushed, always empty in synchronous mode.
+    #[must_use]
+    pub fn pending(&self) -> PendingStats {
+        self.buffer
+            .as_ref()
+            .map_or_else(PendingStats::default, |buffer| buffer.pending())
+    }
+
+    /// Flush buffered increments, a no-op in synchronous mode.
+    ///
+    /// # Errors
+    /// Returns an error if the flush transaction fails.
+    pub async fn flush(&self) -> Result<()> {
+        match &self.buffer {
+            Some(buffer) => buffer.flush().await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Increment the counter of a source by `by`, creating the row if needed,
+/// and record the increment in `source_hits`.
+///
+/// If `name` is an alias, the source it points to is incremented instead.
+///
+/// # Arguments
+/// * `db` - Any connection or transaction.
+/// * `name` - The source name (unique key of `sources`) or one of its aliases.
+/// * `by` - The amount to add to the counter.
+/// * `hit` - UTM parameters, visitor, bot flag and time of the hit.
+///
+/// # Returns
+/// The canonical name and new value of the (human) counter.
+///
+/// # Errors
+/// Returns an error if the upsert query fails.
+pub async fn increment_source<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+    by: i64,
+    hit: &HitDetails,
+) -> Result<SourceCount> {
+    let (humans, bots) = if hit.bot { (0, by) } else { (by, 0) };
+    let visitor = hit.visitor.as_ref().filter(|_| !hit.bot);
+    let utm = &hit.utm;
+
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r"
+        WITH target AS (
+            SELECT COALESCE(
+                (
+                    SELECT sources.name
+                    FROM source_aliases
+                    JOIN sources ON sources.id = source_aliases.source_id
+                    WHERE source_aliases.alias = $1
+                ),
+                $1
+            ) AS name
+        ), counter AS (
+            INSERT INTO sources (name, count, bot_count)
+            SELECT name, $8, $9 FROM target
+            ON CONFLICT (name) DO UPDATE SET
+                count = sources.count + EXCLUDED.count,
+                bot_count = sources.bot_count + EXCLUDED.bot_count,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING id, name, count
+        ), hit AS (
+            INSERT INTO source_hits (
+                source_id, count, is_bot,
+                utm_source, utm_medium, utm_campaign, utm_term, utm_content,
+                created_at
+            )
+            SELECT id, $2, $10, $3, $4, $5, $6, $7, COALESCE($11::TIMESTAMP, CURRENT_TIMESTAMP)
+            FROM counter
+        )
+        SELECT name, count FROM counter
+        ",
+        [
+            name.into(),
+            by.into(),
+            utm.utm_source.clone().into(),
+            utm.utm_medium.clone().into(),
+            utm.utm_campaign.clone().into(),
+            utm.utm_term.clone().into(),
+            utm.utm_content.clone().into(),
+            humans.into(),
+            bots.into(),
+            hit.bot.into(),
+            hit.at.into(),
+        ],
+    );
+
+    let row = db
+        .query_one(stmt)
+        .await
+        .with_context(|| format!("Failed to increment counter for source '{name}'"))?
+        .context("Upsert did not return a row")?;
+
+    let updated = SourceCount {
+        name: row.try_get("", "name")?,
+        count: row.try_get("", "count")?,
+    };
+
+    if let Some(visitor) = visitor {
+        record_visitor(db, &updated.name, visitor).await?;
+    }
+
+    Ok(updated)
+}
+
+/// Record a visitor of a source, counted once per source and day.
+///
+/// A visitor not yet seen that day adds one to `sources.visitor_days`.
+/// Used after the source itself was upserted.
+///
+/// # Errors
+/// Returns an error if the insert fails.
+pub async fn record_visitor<C: ConnectionTrait>(
+    db: &C,
+    name: &str,
+    visitor: &Visitor,
+) -> Result<()> {
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r"
+        WITH visitor AS (
+            INSERT INTO source_visitors (source_id, day, visitor_hash)
+            SELECT sources.id, $2, $3
+            FROM sources
+            WHERE sources.name = COALESCE(
+                (
+                    SELECT sources.name
+                    FROM source_aliases
+                    JOIN sources ON sources.id = source_aliases.source_id
+                    WHERE source_aliases.alias = $1
+                ),
+                $1
+            )
+            ON CONFLICT DO NOTHING
+            RETURNING source_id
+        )
+        UPDATE sources SET visitor_days = visitor_days + 1
+        WHERE id IN (SELECT source_id FROM visitor)
+        ",
+        [name.into(), visitor.day.into(), visitor.hash.clone().into()],
+    );
+
+    db.execute(stmt)
+        .await
+        .with_context(|| format!("Failed to record visitor for source '{name}'"))?;
+
+    Ok(())
+}
+
+/// Delete visitor rows older than yesterday.
+///
+/// Visitor hashes use a salt that rotates every UTC day, so past days can
+/// never be matched again and are already counted in `sources.visitor_days`.
+/// Yesterday is kept for buffered visitors flushed after midnight.
+///
+/// # Returns
+/// The number of deleted rows.
+///
+/// # Errors
+/// Returns an error if the delete fails.
+pub async fn prune_visitors<C: ConnectionTrait>(db: &C) -> Result<u64> {
+    let cutoff = chrono::Utc::now().date_naive() - chrono::Days::new(1);
+
+    let result = db
+        .execute(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "DELETE FROM source_visitors WHERE day < $1",
+            [cutoff.into()],
+        ))
+        .await
+        .context("Failed to prune source visitors")?;
+
+    Ok(result.rows_affected())
+}
+
+/// Spawn the task pruning visitor rows of past days every hour.
+fn spawn_visitor_pruning(db: DatabaseConnection) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(VISITOR_PRUNE_INTERVAL);
+
+        loop {
+            ticker.tick().await;
+
+            match prune_visitors(&db).await {
+                Ok(0) => {}
+                Ok(pruned) => tracing::debug!("Pruned {pruned} visitor rows of past days"),
+                Err(e) => tracing::error!("Visitor pruning failed: {e:#}"),
+            }
+        }
+    });
+}
+
+/// Increment the download counter of a Homebrew bottle by `by`, creating the row if needed.
+///
+/// Bot downloads increment `bot_count` instead of `count`.
+///
+/// # Arguments
+/// * `db` - Any connection or transaction.
+/// * `project` - The formula/project name.
+/// * `version` - The bottle version.
+/// * `platform` - The bottle platform (e.g. `arm64_sequoia`).
+/// * `by` - The amount to add to the counter.
+/// * `bot` - Whether the downloads come from a bot.
+///
+/// # Returns
+/// The new value of the (human) counter.
+///
+/// # Errors
+/// Returns an error if the upsert query fails.
+pub async fn increment_brew_download<C: ConnectionTrait>(
+    db: &C,
+    project: &str,
+    version: &str,
+    platform: &str,
+    by: i64,
+    bot: bool,
+) -> Result<i64> {
+    let (humans, bots) = if bot { (0, by) } else { (by, 0) };
+
+    // Conflict target matches the `idx_brew_downloads_unique` index
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r"
+        INSERT INTO brew_downloads (project, version, platform, count, bot_count)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (project, version, platform) DO UPDATE SET
+            count = brew_downloads.count + EXCLUDED.count,
+            bot_count = brew_downloads.bot_count + EXCLUDED.bot_count
+        RETURNING count
+        ",
+        [
+            project.into(),
+            version.into(),
+            platform.into(),
+            humans.into(),
+            bots.into(),
+        ],
+    );
+
+    fetch_count(db, stmt).await.with_context(|| {
+        format!("Failed to increment brew download counter for {project} {version} ({platform})")
+    })
+}
+
+/// Run an upsert statement and read back the `count` column it returns.
+async fn fetch_count<C: ConnectionTrait>(db: &C, stmt: Statement) -> Result<i64> {
+    let row = db
+        .query_one(stmt)
+        .await?
+        .context("Upsert did not return a row")?;
+
+    Ok(row.try_get::<i64>("", "count")?)
+}
@@ -1,11 +1,7 @@
 use anyhow::{Context, Result};
-use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Database, DatabaseConnection, EntityTrait, QueryFilter, Set,
-};
+use sea_orm::{Database, DatabaseConnection};
 use tracing::info;
 
-use crate::entities::{prelude::*, sources};
-
 /// Initialize the database connection.
 ///
 /// # Returns
@@ -27,48 +23,3 @@ pub async fn init_pool() -> Result<DatabaseConnection> {
 
     Ok(db)
 }
-
-/// Increment the counter for a given source.
-///
-/// # Arguments
-/// * `db` - A reference to the `DatabaseConnection` for database operations.
-/// * `source` - The name of the source to increment.
-///
-/// # Returns
-/// A `Result` indicating success or failure.
-///
-/// # Errors
-/// Returns an error if:
-/// - Database query fails
-/// - Database transaction fails
-pub async fn increment_source_in_db(db: &DatabaseConnection, source: &str) -> Result<()> {
-    // Try to find existing source
-    let existing = Sources::find()
-        .filter(sources::Column::Name.eq(source))
-        .one(db)
-        .await
-        .context("Failed to query existing source from database")?;
-
-    if let Some(model) = existing {
-        // Update existing source
-        let mut active_model: sources::ActiveModel = model.into();
-        active_model.count = Set(active_model.count.unwrap() + 1);
-        active_model
-            .update(db)
-            .await
-            .with_context(|| format!("Failed to update counter for source '{source}'"))?;
-    } else {
-        // Insert new source
-        let new_source = sources::ActiveModel {
-            name: Set(source.to_string()),
-            count: Set(1),
-            ..Default::default()
-        };
-        new_source
-            .insert(db)
-            .await
-            .with_context(|| format!("Failed to create new source '{source}'"))?;
-    }
-
-    Ok(())
-}
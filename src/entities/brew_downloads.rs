@@ -9,7 +9,8 @@ pub struct Model {
     pub project: String,
     pub version: String,
     pub platform: String,
-    pub count: i32,
+    pub count: i64,
+    pub bot_count: i64,
     #[sea_orm(created_at)]
     pub created_at: chrono::NaiveDateTime,
     #[sea_orm(updated_at)]
@@ -1,4 +1,8 @@
 pub mod brew_downloads;
 pub mod prelude;
+pub mod sea_orm_active_enums;
+pub mod source_aliases;
+pub mod source_hits;
+pub mod source_visitors;
 pub mod sources;
 pub mod stickers;
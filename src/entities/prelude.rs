@@ -1,3 +1,6 @@
 pub use super::brew_downloads::Entity as BrewDownloads;
+pub use super::source_aliases::Entity as SourceAliases;
+pub use super::source_hits::Entity as SourceHits;
+pub use super::source_visitors::Entity as SourceVisitors;
 pub use super::sources::Entity as Sources;
 pub use super::stickers::Entity as Stickers;
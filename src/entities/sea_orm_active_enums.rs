@@ -0,0 +1,15 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(16))")]
+#[serde(rename_all = "lowercase")]
+pub enum Visibility {
+    #[sea_orm(string_value = "public")]
+    Public,
+    #[default]
+    #[sea_orm(string_value = "private")]
+    Private,
+}
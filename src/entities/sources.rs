@@ -8,7 +8,9 @@ pub struct Model {
     pub id: i32,
     #[sea_orm(unique)]
     pub name: String,
-    pub count: i32,
+    pub count: i64,
+    pub bot_count: i64,
+    pub visitor_days: i64,
     #[sea_orm(created_at)]
     pub created_at: chrono::NaiveDateTime,
     #[sea_orm(updated_at)]
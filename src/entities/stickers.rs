@@ -1,6 +1,8 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use super::sea_orm_active_enums::Visibility;
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "stickers")]
 pub struct Model {
@@ -11,6 +13,10 @@ pub struct Model {
     pub longitude: f64,
     pub place_name: String,
     pub pictures: serde_json::Value,
+    pub visibility: Visibility,
+    pub placed_at: Option<chrono::NaiveDateTime>,
+    pub country_code: Option<String>,
+    pub region: Option<String>,
     #[sea_orm(created_at)]
     pub created_at: chrono::NaiveDateTime,
     #[sea_orm(updated_at)]
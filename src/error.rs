@@ -9,9 +9,29 @@ use axum::{
     http::StatusCode,
     response::{IntoResponse, Response},
 };
+use serde::Serialize;
 use serde_json::json;
 use thiserror::Error;
 
+/// A single invalid field of a request payload
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    /// Path of the field, e.g. `latitude` or `pictures[1]`
+    pub field: String,
+    /// Why the value was refused
+    pub message: String,
+}
+
+impl FieldError {
+    /// Create a field error
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
 /// API error type for the public HTTP boundary.
 ///
 /// This enum represents all possible errors that can occur in the API,
@@ -22,6 +42,10 @@ pub enum ApiError {
     #[error("validation failed: {0}")]
     ValidationFailed(String),
 
+    /// Validation error listing every invalid field of the payload
+    #[error("validation failed: {} invalid field(s)", .0.len())]
+    InvalidFields(Vec<FieldError>),
+
     /// Resource not found error
     #[error("resource not found: {0}")]
     NotFound(String),
@@ -40,7 +64,7 @@ impl ApiError {
     #[must_use]
     pub const fn status_code(&self) -> StatusCode {
         match self {
-            Self::ValidationFailed(_) => StatusCode::BAD_REQUEST,
+            Self::ValidationFailed(_) | Self::InvalidFields(_) => StatusCode::BAD_REQUEST,
             Self::NotFound(_) => StatusCode::NOT_FOUND,
             Self::Internal(_) | Self::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -75,11 +99,19 @@ impl IntoResponse for ApiError {
             _ => self.to_string(),
         };
 
-        let body = Json(json!({
-            "error": {
-                "message": error_message
-            }
-        }));
+        let body = match &self {
+            Self::InvalidFields(fields) => Json(json!({
+                "error": {
+                    "message": error_message,
+                    "fields": fields
+                }
+            })),
+            _ => Json(json!({
+                "error": {
+                    "message": error_message
+                }
+            })),
+        };
 
         (status, body).into_response()
     }
@@ -1,5 +1,8 @@
+pub mod bot;
 pub mod brew;
+pub mod client;
 pub mod config;
+pub mod counter;
 pub mod db;
 pub mod entities;
 pub mod error;
@@ -11,6 +14,7 @@ pub mod source;
 pub mod static_files;
 pub mod stats;
 pub mod sticker;
+pub mod track;
 
 // Re-export error types for convenience
 pub use error::{ApiError, ApiResult};
@@ -5,12 +5,22 @@ use axum::{
     routing::get,
 };
 use serde_json::json;
-use std::sync::Arc;
+use std::{net::SocketAddr, sync::Arc};
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use vps_back::{
-    brew, config::Config, data_response, db::init_pool, github_stats, middlewares, source,
-    static_files::static_files_service, stats, sticker,
+    bot::BotFilter,
+    brew,
+    config::Config,
+    counter::Counters,
+    data_response,
+    db::init_pool,
+    github_stats, middlewares, source,
+    source::{tracker::SourceTracker, visitor::VisitorHasher},
+    static_files::static_files_service,
+    stats, sticker,
+    sticker::{cluster::ClusterCache, geocode::Geocoder, pictures::PictureStore},
+    track,
 };
 
 #[tokio::main]
@@ -44,6 +54,34 @@ async fn main() {
         std::process::exit(1);
     });
 
+    // Bot classifier, the pattern file and IP ranges are validated at startup
+    let bots = BotFilter::new(
+        config.bot_patterns_file.as_deref(),
+        &config.bot_ip_ranges,
+        config.trust_proxy_headers,
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Bot filter configuration error: {e:#}");
+        std::process::exit(1);
+    });
+
+    // Reverse geocoder, the dataset is loaded in memory at startup
+    let geocoder = Geocoder::load(
+        config.geocoder_cities_file.as_deref(),
+        config.geocoder_regions_file.as_deref(),
+    )
+    .unwrap_or_else(|e| {
+        eprintln!("Geocoder configuration error: {e:#}");
+        std::process::exit(1);
+    });
+
+    // Counter service, spawns the flush task in buffered mode
+    let counters = Counters::from_config(db.clone(), &config);
+
+    // Pictures uploaded before variants existed get them in the background
+    let pictures = PictureStore::new(config.static_dir.clone(), config.sticker_picture_max_bytes);
+    pictures.spawn_variant_backfill();
+
     // Configure CORS
     let allowed_origins = config
         .allowed_origins
@@ -60,6 +98,7 @@ async fn main() {
             Method::GET,
             Method::POST,
             Method::PUT,
+            Method::PATCH,
             Method::DELETE,
             Method::OPTIONS,
         ])
@@ -78,7 +117,11 @@ async fn main() {
     // Create API router with protected routes
     let api_router = Router::new()
         .nest("/source", source::router())
-        .nest("/stickers", sticker::router())
+        .nest(
+            "/stickers",
+            sticker::router(config.sticker_picture_max_bytes),
+        )
+        .merge(sticker::export_router())
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             middlewares::auth::validate_api_key,
@@ -91,9 +134,31 @@ async fn main() {
         .nest("/secure", api_router)
         .nest("/brew", brew::router())
         .nest("/stats", stats::router())
+        .nest("/track", track::router())
+        .nest("/stickers", sticker::public_router())
         .layer(cors)
         .layer(middlewares::tracing::create_tracing_layer())
         .layer(axum::Extension(config.static_dir.clone()))
+        .layer(axum::Extension(counters.clone()))
+        .layer(axum::Extension(config.source_normalization))
+        .layer(axum::Extension(config.source_validation.clone()))
+        .layer(axum::Extension(config.referrer_sources.clone()))
+        .layer(axum::Extension(track::RedirectAllowlist::new(
+            &config.track_redirect_hosts,
+        )))
+        .layer(axum::Extension(SourceTracker::new(
+            counters.clone(),
+            config.source_normalization,
+            config.source_validation.clone(),
+            VisitorHasher::new(config.trust_proxy_headers),
+            bots.clone(),
+        )))
+        .layer(axum::Extension(bots))
+        .layer(axum::Extension(config.sticker_public_fuzzing))
+        .layer(axum::Extension(pictures.clone()))
+        .layer(axum::Extension(geocoder))
+        .layer(axum::Extension(ClusterCache::default()))
+        .layer(axum::Extension(config.public_base_url.clone()))
         .with_state(db);
 
     let addr = format!("{}:{}", config.host, config.port);
@@ -110,7 +175,45 @@ async fn main() {
         tracing::warn!("GITHUB_TOKEN not set — GitHub stats will not be generated");
     }
 
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await
+    .unwrap();
+
+    // Write whatever the counter buffer still holds before exiting
+    if let Err(e) = counters.flush().await {
+        tracing::error!("Failed to flush counters on shutdown: {e:#}");
+    }
+}
+
+/// Resolves when the process receives Ctrl+C or SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {}
+        () = terminate => {}
+    }
+
+    tracing::info!("Shutdown signal received");
 }
 
 /// Handles GET requests to the root path ("/").
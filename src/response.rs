@@ -57,6 +57,9 @@ impl Metadata {
     }
 
     /// Create metadata for paginated responses
+    ///
+    /// `self_link` may carry a query string (e.g. active filters), which is
+    /// kept in the `next` and `prev` links.
     #[must_use]
     #[allow(dead_code)]
     pub fn paginated(page: u32, limit: u32, total_count: u32, self_link: String) -> Self {
@@ -67,14 +70,22 @@ impl Metadata {
             total_count - (page - 1) * limit
         };
 
+        let separator = if self_link.contains('?') { '&' } else { '?' };
+
         let next = if page < total_pages {
-            Some(format!("{}?page={}&limit={}", self_link, page + 1, limit))
+            Some(format!(
+                "{self_link}{separator}page={}&limit={limit}",
+                page + 1
+            ))
         } else {
             None
         };
 
         let prev = if page > 1 {
-            Some(format!("{}?page={}&limit={}", self_link, page - 1, limit))
+            Some(format!(
+                "{self_link}{separator}page={}&limit={limit}",
+                page - 1
+            ))
         } else {
             None
         };
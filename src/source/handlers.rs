@@ -2,34 +2,72 @@
 
 use anyhow::Context;
 use axum::{
-    Json,
-    extract::{Query, State},
+    Extension, Json,
+    body::Bytes,
+    extract::{ConnectInfo, Path, Query, State},
+    http::HeaderMap,
 };
+use chrono::{NaiveDate, NaiveDateTime};
 use sea_orm::{
-    ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
-    QuerySelect,
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DatabaseTransaction,
+    DbBackend, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, SqlErr,
+    Statement, TransactionTrait, Unchanged,
+    sea_query::{Expr, OnConflict},
 };
 use serde_json::json;
+use std::{
+    collections::{BTreeMap, HashMap},
+    net::SocketAddr,
+};
 use tracing::info;
 
 use crate::{
+    bot::IncludeBotsParams,
+    counter::HitDetails,
     data_response, data_response_with_metadata,
-    entities::{prelude::*, sources},
-    error::ApiResult,
+    entities::{prelude::*, source_aliases, source_hits, sources},
+    error::{ApiError, ApiResult},
     pagination::PaginationParams,
     response::Metadata,
-    source::models::SourceRequest,
+    source::{
+        models::{
+            BatchEntry, BatchEntryError, CampaignParams, CampaignStats, MergeRequest,
+            SourceListParams, SourceRequest, SourceResponse, SourceStatsParams, SourceStatsView,
+            SourceView, TimeseriesBucket, TimeseriesParams, UpdateSourceRequest,
+        },
+        normalize::SourceNormalization,
+        referrer::ReferrerSources,
+        tracker::SourceTracker,
+        utm::Utm,
+        validate::{self, SourceValidation},
+    },
 };
 
+/// Maximum number of entries accepted by a single batch increment
+const MAX_BATCH_ENTRIES: usize = 1_000;
+
+/// Default time series range when `from` is omitted
+const DEFAULT_TIMESERIES_DAYS: i64 = 30;
+
+/// Maximum number of buckets a single time series request may return
+const MAX_TIMESERIES_BUCKETS: i64 = 1_000;
+
 /// Handles GET requests for source statistics (flat map, no pagination).
 ///
+/// Bot hits are left out unless `include_bots=true`, in which case they are
+/// added to the counts. With `view=stats` each source maps to its `hits` and
+/// `uniques` (daily unique visitors, summed over days), plus `bots` when
+/// they are included.
+///
 /// # Returns
-/// JSON object mapping each source name to its count.
+/// JSON object mapping each source name to its count, or to its statistics.
 ///
 /// # Errors
 /// Returns 500 on database failure.
 pub async fn get_source_stats(
     State(db): State<DatabaseConnection>,
+    Query(params): Query<IncludeBotsParams>,
+    Query(stats): Query<SourceStatsParams>,
 ) -> ApiResult<Json<serde_json::Value>> {
     use serde_json::json;
 
@@ -43,18 +81,297 @@ pub async fn get_source_stats(
 
     let mut map = serde_json::Map::new();
     for row in rows {
-        map.insert(row.name, json!(row.count));
+        let hits = if params.include_bots {
+            row.count + row.bot_count
+        } else {
+            row.count
+        };
+        let value = match stats.view {
+            SourceStatsView::Map => json!(hits),
+            SourceStatsView::Stats if params.include_bots => json!({
+                "hits": hits,
+                "uniques": row.visitor_days,
+                "bots": row.bot_count,
+            }),
+            SourceStatsView::Stats => json!({ "hits": hits, "uniques": row.visitor_days }),
+        };
+        map.insert(row.name, value);
     }
 
     Ok(data_response(serde_json::Value::Object(map)))
 }
 
+/// Parses a query string timestamp, either RFC 3339 or a plain `YYYY-MM-DD` date.
+///
+/// RFC 3339 values are converted to UTC.
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|dt| dt.naive_utc())
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+/// Parses an optional timestamp query parameter, failing with a validation error.
+fn parse_timestamp_param(name: &str, value: Option<&str>) -> ApiResult<Option<NaiveDateTime>> {
+    value
+        .map(|value| {
+            parse_timestamp(value)
+                .ok_or_else(|| ApiError::validation(format!("Invalid `{name}` timestamp: {value}")))
+        })
+        .transpose()
+}
+
+/// Looks up the id of an optional source filter.
+///
+/// Like hits, the filter is matched as stored, then normalized, then through
+/// the aliases of either form, so `?source=X.com` finds the hits counted on
+/// the source it was merged into.
+///
+/// # Errors
+/// Returns 404 if the source does not exist, 500 on database failure.
+async fn find_source_id(
+    db: &DatabaseConnection,
+    normalization: &SourceNormalization,
+    name: Option<&str>,
+) -> ApiResult<Option<i32>> {
+    let Some(name) = name else {
+        return Ok(None);
+    };
+
+    let normalized = normalization.apply(name);
+    let candidates = if normalized == name {
+        vec![name]
+    } else {
+        vec![name, normalized.as_str()]
+    };
+
+    for candidate in &candidates {
+        let source = Sources::find()
+            .filter(sources::Column::Name.eq(*candidate))
+            .one(db)
+            .await
+            .context("Failed to fetch source")?;
+
+        if let Some(source) = source {
+            return Ok(Some(source.id));
+        }
+    }
+
+    let alias = SourceAliases::find()
+        .filter(source_aliases::Column::Alias.is_in(candidates))
+        .order_by_asc(source_aliases::Column::Id)
+        .one(db)
+        .await
+        .context("Failed to resolve source alias")?;
+
+    alias.map(|alias| Some(alias.source_id)).ok_or_else(|| {
+        ApiError::not_found(format!(
+            "Source '{}' not found",
+            validate::echo(&normalized)
+        ))
+    })
+}
+
+/// Handles GET requests for a source hit time series.
+///
+/// Buckets with no hits are included with a count of zero. Bot hits are
+/// left out unless `include_bots=true`.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(normalization)` - The configured source normalization.
+/// * `Query(params)` - Source, range (`from` inclusive, `to` exclusive) and granularity.
+///
+/// # Returns
+/// JSON object containing the range, the total and the ordered buckets.
+///
+/// # Errors
+/// * 400 if the range is invalid or would produce too many buckets
+/// * 404 if the source does not exist
+/// * 500 on database failure
+pub async fn get_source_timeseries(
+    State(db): State<DatabaseConnection>,
+    Extension(normalization): Extension<SourceNormalization>,
+    Query(params): Query<TimeseriesParams>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!(
+        "GET `/stats/sources/timeseries` endpoint called with source={:?}, granularity={}",
+        params.source,
+        params.granularity.as_str()
+    );
+
+    let to = parse_timestamp_param("to", params.to.as_deref())?
+        .unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+    let from = parse_timestamp_param("from", params.from.as_deref())?
+        .unwrap_or_else(|| to - chrono::Duration::days(DEFAULT_TIMESERIES_DAYS));
+
+    if from >= to {
+        return Err(ApiError::validation("`from` must be before `to`"));
+    }
+
+    let granularity = params.granularity;
+    if (to - from).num_seconds() / granularity.min_seconds() > MAX_TIMESERIES_BUCKETS {
+        return Err(ApiError::validation(format!(
+            "Range too large for `{}` granularity (max {MAX_TIMESERIES_BUCKETS} buckets)",
+            granularity.as_str()
+        )));
+    }
+
+    let source_id = find_source_id(&db, &normalization, params.source.as_deref()).await?;
+
+    // The granularity comes from a closed enum, so it is safe to inline in the query
+    let unit = granularity.as_str();
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        format!(
+            r"
+            WITH hits AS (
+                SELECT date_trunc('{unit}', created_at) AS bucket, SUM(count) AS hits
+                FROM source_hits
+                WHERE created_at >= $1
+                  AND created_at < $2
+                  AND ($3::INT IS NULL OR source_id = $3)
+                  AND ($4 OR NOT is_bot)
+                GROUP BY 1
+            )
+            SELECT series.bucket, COALESCE(hits.hits, 0)::BIGINT AS hits
+            FROM generate_series(
+                date_trunc('{unit}', $1::TIMESTAMP),
+                $2::TIMESTAMP - INTERVAL '1 microsecond',
+                INTERVAL '1 {unit}'
+            ) AS series(bucket)
+            LEFT JOIN hits ON hits.bucket = series.bucket
+            ORDER BY series.bucket
+            "
+        ),
+        [
+            from.into(),
+            to.into(),
+            source_id.into(),
+            params.include_bots.into(),
+        ],
+    );
+
+    let rows = db
+        .query_all(stmt)
+        .await
+        .context("Failed to fetch source time series")?;
+
+    let buckets = rows
+        .into_iter()
+        .map(|row| {
+            let bucket: NaiveDateTime = row.try_get("", "bucket")?;
+            Ok(TimeseriesBucket {
+                bucket: bucket.and_utc().to_rfc3339(),
+                hits: row.try_get("", "hits")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sea_orm::DbErr>>()
+        .context("Failed to read source time series rows")?;
+
+    let total: i64 = buckets.iter().map(|b| b.hits).sum();
+
+    Ok(data_response(json!({
+        "source": params.source,
+        "granularity": granularity,
+        "from": from.and_utc().to_rfc3339(),
+        "to": to.and_utc().to_rfc3339(),
+        "total": total,
+        "buckets": buckets,
+    })))
+}
+
+/// Handles GET requests for campaign statistics.
+///
+/// Groups hits carrying a `utm_campaign` by campaign and medium, most hits first.
+/// Bot hits are left out unless `include_bots=true`.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(normalization)` - The configured source normalization.
+/// * `Query(params)` - Optional source and range (`from` inclusive, `to` exclusive) filters.
+///
+/// # Returns
+/// JSON object containing the list of campaign/medium pairs and their hit counts.
+///
+/// # Errors
+/// * 400 if a timestamp is invalid
+/// * 404 if the source does not exist
+/// * 500 on database failure
+pub async fn get_campaign_stats(
+    State(db): State<DatabaseConnection>,
+    Extension(normalization): Extension<SourceNormalization>,
+    Query(params): Query<CampaignParams>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!(
+        "GET `/stats/campaigns` endpoint called with source={:?}",
+        params.source
+    );
+
+    let from = parse_timestamp_param("from", params.from.as_deref())?;
+    let to = parse_timestamp_param("to", params.to.as_deref())?;
+    let source_id = find_source_id(&db, &normalization, params.source.as_deref()).await?;
+
+    let stmt = Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r"
+        SELECT utm_campaign AS campaign, utm_medium AS medium, SUM(count)::BIGINT AS hits
+        FROM source_hits
+        WHERE utm_campaign IS NOT NULL
+          AND ($1::TIMESTAMP IS NULL OR created_at >= $1)
+          AND ($2::TIMESTAMP IS NULL OR created_at < $2)
+          AND ($3::INT IS NULL OR source_id = $3)
+          AND ($4 OR NOT is_bot)
+        GROUP BY utm_campaign, utm_medium
+        ORDER BY hits DESC, campaign, medium
+        ",
+        [
+            from.into(),
+            to.into(),
+            source_id.into(),
+            params.include_bots.into(),
+        ],
+    );
+
+    let rows = db
+        .query_all(stmt)
+        .await
+        .context("Failed to fetch campaign statistics")?;
+
+    let campaigns = rows
+        .into_iter()
+        .map(|row| {
+            Ok(CampaignStats {
+                campaign: row.try_get("", "campaign")?,
+                medium: row.try_get("", "medium")?,
+                hits: row.try_get("", "hits")?,
+            })
+        })
+        .collect::<Result<Vec<_>, sea_orm::DbErr>>()
+        .context("Failed to read campaign statistics rows")?;
+
+    Ok(data_response(json!({
+        "campaigns": campaigns,
+    })))
+}
+
 /// Handles GET requests to the sources path ("/sources").
 /// Fetches all sources and their counts from the database with pagination.
 ///
+/// With `view=stats`, each source maps to its hits and uniques. With
+/// `view=detailed`, sources are returned as a list of objects carrying their
+/// id, first seen time, last human hit and last change instead of a name map.
+/// The view is kept in the pagination links.
+///
 /// # Arguments
 /// * `State(db)` - The database connection.
 /// * `Query(params)` - Pagination parameters (page, limit).
+/// * `Query(list)` - The listing shape (`view`).
 ///
 /// # Returns
 /// * `ApiResult<Json<Value>>` - JSON response containing the sources and their counts with pagination metadata.
@@ -64,10 +381,11 @@ pub async fn get_source_stats(
 pub async fn get_all_sources(
     State(db): State<DatabaseConnection>,
     Query(mut params): Query<PaginationParams>,
+    Query(list): Query<SourceListParams>,
 ) -> ApiResult<Json<serde_json::Value>> {
     info!(
-        "GET `/sources` endpoint called with page={}, limit={}",
-        params.page, params.limit
+        "GET `/sources` endpoint called with page={}, limit={}, view={:?}",
+        params.page, params.limit, list.view
     );
 
     // Validate pagination parameters
@@ -92,60 +410,741 @@ pub async fn get_all_sources(
         .await
         .context("Failed to fetch sources from database")?;
 
-    // Convert rows into a map of name to count
-    let mut sources_map = serde_json::Map::new();
-    for model in sources_list {
-        sources_map.insert(model.name, json!(model.count));
-    }
+    let sources = match list.view {
+        SourceView::Detailed => {
+            let last_seen = fetch_last_seen(&db, sources_list.iter().map(|model| model.id)).await?;
 
-    // Build metadata
-    let metadata = Metadata::paginated(
-        params.page,
-        params.limit,
-        total_count,
-        "/secure/source".to_string(),
-    );
+            let detailed: Vec<SourceResponse> = sources_list
+                .into_iter()
+                .map(|model| SourceResponse {
+                    id: i64::from(model.id),
+                    last_seen_at: last_seen.get(&model.id).map(|at| at.and_utc().to_rfc3339()),
+                    name: model.name,
+                    count: model.count,
+                    created_at: model.created_at.and_utc().to_rfc3339(),
+                    updated_at: model.updated_at.and_utc().to_rfc3339(),
+                })
+                .collect();
+
+            json!(detailed)
+        }
+        SourceView::Map => {
+            // Convert rows into a map of name to count
+            let mut sources_map = serde_json::Map::new();
+            for model in sources_list {
+                sources_map.insert(model.name, json!(model.count));
+            }
+
+            serde_json::Value::Object(sources_map)
+        }
+        SourceView::Stats => {
+            // Convert rows into a map of name to hits and uniques
+            let mut sources_map = serde_json::Map::new();
+            for model in sources_list {
+                sources_map.insert(
+                    model.name,
+                    json!({ "hits": model.count, "uniques": model.visitor_days }),
+                );
+            }
+
+            serde_json::Value::Object(sources_map)
+        }
+    };
+
+    // Build metadata, keeping the view in the page links
+    let self_link = match list.view {
+        SourceView::Map => "/secure/source".to_string(),
+        view => format!("/secure/source?view={}", view.as_str()),
+    };
+    let metadata = Metadata::paginated(params.page, params.limit, total_count, self_link);
 
     Ok(data_response_with_metadata(
         json!({
-            "sources": sources_map
+            "sources": sources
         }),
         &metadata,
     ))
 }
 
+/// Finds the time of the last human hit of the given sources.
+///
+/// # Returns
+/// A map of source id to last hit time, sources without hits are omitted.
+///
+/// # Errors
+/// Returns 500 on database failure.
+async fn fetch_last_seen(
+    db: &DatabaseConnection,
+    source_ids: impl IntoIterator<Item = i32>,
+) -> ApiResult<HashMap<i32, NaiveDateTime>> {
+    let rows: Vec<(i32, NaiveDateTime)> = SourceHits::find()
+        .select_only()
+        .column(source_hits::Column::SourceId)
+        .column_as(source_hits::Column::CreatedAt.max(), "last_seen_at")
+        .filter(source_hits::Column::SourceId.is_in(source_ids))
+        .filter(source_hits::Column::IsBot.eq(false))
+        .group_by(source_hits::Column::SourceId)
+        .into_tuple()
+        .all(db)
+        .await
+        .context("Failed to fetch last source hits")?;
+
+    Ok(rows.into_iter().collect())
+}
+
 /// Handles POST requests to the source path ("/source").
 /// Increments the count for a given source in the database.
 ///
+/// The name is normalized first, and aliases are resolved to their source.
+/// UTM parameters are taken from the payload, completed by those of
+/// `landing_url`. The source defaults to `utm_source` when omitted, then to
+/// the mapped `Referer`/`Origin` host, then to `direct`. The body may be
+/// omitted entirely.
+///
 /// # Arguments
-/// * `State(db)` - The database connection.
-/// * `Json(payload)` - The request payload containing the source name.
+/// * `Extension(tracker)` - The source hit tracker.
+/// * `Extension(referrers)` - The referrer host to source mapping.
+/// * `ConnectInfo(peer)` - The client socket address, when available.
+/// * `headers` - The request headers, used to identify the visitor and the referrer.
+/// * `body` - The optional JSON payload containing the source name and UTM parameters.
 ///
 /// # Returns
-/// * `ApiResult<Json<Value>>` - JSON response containing the updated source count.
+/// * `ApiResult<Json<Value>>` - JSON response containing the updated source count,
+///   or `null` when counters are buffered.
 ///
 /// # Errors
-/// Returns an error if the database operation fails.
+/// Returns 400 if the body is not a valid payload or the source is refused,
+/// 500 if the database operation fails.
 pub async fn increment_source(
+    Extension(tracker): Extension<SourceTracker>,
+    Extension(referrers): Extension<ReferrerSources>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> ApiResult<Json<serde_json::Value>> {
+    let payload: SourceRequest = if body.iter().all(u8::is_ascii_whitespace) {
+        SourceRequest::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| ApiError::validation(format!("Invalid request body: {e}")))?
+    };
+
+    info!("POST `/source` endpoint called for: {:?}", payload.source);
+
+    let utm = payload.utm.or(payload
+        .landing_url
+        .as_deref()
+        .map(Utm::from_url)
+        .unwrap_or_default());
+
+    let source = payload
+        .source
+        .or_else(|| utm.utm_source.clone())
+        .unwrap_or_else(|| referrers.source(&headers));
+
+    let counts = tracker
+        .record_hit(&source, &utm, &headers, peer.map(|ConnectInfo(addr)| addr))
+        .await?;
+
+    Ok(data_response(counts))
+}
+
+/// Handles POST requests to the batch source path ("/source/batch").
+/// Applies a list of `{source, count}` increments, with optional UTM parameters,
+/// in a single transaction.
+///
+/// Entries with a refused source name or a non-positive count are reported in
+/// `errors` without aborting the rest of the batch. Valid entries for the same source are
+/// summed and applied in name order. Batches are always written directly, even
+/// when counters are buffered, since they are already aggregated. Names are
+/// normalized and validated inside the transaction, against the aliases and
+/// sources of the whole batch looked up at once, and totals are keyed by
+/// canonical source name.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(normalization)` - The configured source normalization.
+/// * `Extension(validation)` - The configured source name rules.
+/// * `Json(entries)` - The list of increments to apply.
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the new total of each incremented source
+///   and the rejected entries.
+///
+/// # Errors
+/// Returns 400 if the batch is too large, 500 if the transaction fails.
+pub async fn increment_sources_batch(
+    State(db): State<DatabaseConnection>,
+    Extension(normalization): Extension<SourceNormalization>,
+    Extension(validation): Extension<SourceValidation>,
+    Json(entries): Json<Vec<serde_json::Value>>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!(
+        "POST `/source/batch` endpoint called with {} entries",
+        entries.len()
+    );
+
+    if entries.len() > MAX_BATCH_ENTRIES {
+        return Err(ApiError::validation(format!(
+            "Batch too large: {} entries (max {MAX_BATCH_ENTRIES})",
+            entries.len()
+        )));
+    }
+
+    // Entries are parsed one by one, so a malformed entry only rejects itself
+    let (entries, mut errors) = parse_batch(entries);
+
+    let txn = db.begin().await.context("Failed to start transaction")?;
+
+    // Aliases and existing sources of the whole batch are fetched at once
+    let sources: Vec<String> = entries
+        .iter()
+        .map(|(_, entry)| normalization.apply(&entry.source))
+        .collect();
+    let known = validation
+        .lookup(&txn, sources.iter().map(String::as_str))
+        .await?;
+
+    let mut increments: BTreeMap<(String, Utm), i64> = BTreeMap::new();
+
+    for ((index, entry), source) in entries.into_iter().zip(sources) {
+        let message = match validation.validate_known(&known, &source) {
+            Err(ApiError::ValidationFailed(reason)) => Some(reason),
+            Err(e) => return Err(e),
+            Ok(()) if entry.count <= 0 => {
+                Some(format!("count must be positive, got {}", entry.count))
+            }
+            Ok(()) => None,
+        };
+
+        if let Some(message) = message {
+            errors.push(BatchEntryError {
+                index,
+                source: validate::echo(&entry.source),
+                message,
+            });
+            continue;
+        }
+
+        let utm = entry.utm.or(entry
+            .landing_url
+            .as_deref()
+            .map(Utm::from_url)
+            .unwrap_or_default());
+
+        let total = increments.entry((source, utm)).or_insert(0);
+        *total = total.saturating_add(entry.count);
+    }
+
+    // Sorted keys keep row lock order consistent across concurrent batches
+    let mut totals = serde_json::Map::new();
+    for ((source, utm), count) in increments {
+        let hit = HitDetails {
+            utm,
+            ..HitDetails::default()
+        };
+        let updated = crate::counter::increment_source(&txn, &source, count, &hit).await?;
+        totals.insert(updated.name, json!(updated.count));
+    }
+
+    txn.commit()
+        .await
+        .context("Failed to commit batch increment")?;
+
+    errors.sort_by_key(|error| error.index);
+
+    Ok(data_response(json!({
+        "totals": totals,
+        "errors": errors,
+    })))
+}
+
+/// Parse the entries of a batch increment request one by one.
+///
+/// # Returns
+/// The well-formed entries with their index, and an error for each malformed one.
+fn parse_batch(values: Vec<serde_json::Value>) -> (Vec<(usize, BatchEntry)>, Vec<BatchEntryError>) {
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, value) in values.into_iter().enumerate() {
+        let source = value
+            .get("source")
+            .and_then(serde_json::Value::as_str)
+            .map(validate::echo)
+            .unwrap_or_default();
+
+        match serde_json::from_value::<BatchEntry>(value) {
+            Ok(entry) => entries.push((index, entry)),
+            Err(e) => errors.push(BatchEntryError {
+                index,
+                source,
+                message: format!("invalid entry: {e}"),
+            }),
+        }
+    }
+
+    (entries, errors)
+}
+
+/// Handles POST requests to the merge path ("/source/merge").
+/// Folds the count, hit history and visitors of `from` into `into`, deletes `from`
+/// and records it as an alias so future hits go to `into`.
+///
+/// `from` is looked up as stored, then normalized. `into` is normalized,
+/// resolved through existing aliases and validated. Aliases of `from` are
+/// moved to `into`.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(normalization)` - The configured source normalization.
+/// * `Extension(validation)` - The configured source name rules.
+/// * `Json(payload)` - The source to fold and the source receiving it.
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the merged count and the new total.
+///
+/// # Errors
+/// * 400 if `into` is refused or both names resolve to the same source
+/// * 404 if `from` does not exist
+/// * 500 on database failure
+pub async fn merge_sources(
+    State(db): State<DatabaseConnection>,
+    Extension(normalization): Extension<SourceNormalization>,
+    Extension(validation): Extension<SourceValidation>,
+    Json(payload): Json<MergeRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!(
+        "POST `/source/merge` endpoint called: {} -> {}",
+        payload.from, payload.into
+    );
+
+    let into = normalization.apply(&payload.into);
+
+    let txn = db.begin().await.context("Failed to start transaction")?;
+    validation.validate(&txn, &into).await?;
+
+    // Legacy names such as `Twitter` are matched as stored first, so they
+    // can be folded into their normalized form
+    let source = lock_source(&txn, &normalization, &payload.from).await?;
+
+    // Merging into an alias means merging into the source it points to
+    let alias = SourceAliases::find()
+        .filter(source_aliases::Column::Alias.eq(&into))
+        .one(&txn)
+        .await
+        .context("Failed to resolve merge target alias")?;
+    let into = match alias {
+        Some(alias) => Sources::find_by_id(alias.source_id)
+            .one(&txn)
+            .await
+            .context("Failed to fetch merge target")?
+            .map_or(into, |target| target.name),
+        None => into,
+    };
+
+    if into == source.name {
+        return Err(ApiError::validation(format!(
+            "Cannot merge source '{}' into itself",
+            source.name
+        )));
+    }
+
+    let target = Sources::insert(sources::ActiveModel {
+        name: Set(into.clone()),
+        count: Set(source.count),
+        bot_count: Set(source.bot_count),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(sources::Column::Name)
+            .value(
+                sources::Column::Count,
+                Expr::col((Sources, sources::Column::Count)).add(source.count),
+            )
+            .value(
+                sources::Column::BotCount,
+                Expr::col((Sources, sources::Column::BotCount)).add(source.bot_count),
+            )
+            .value(sources::Column::UpdatedAt, Expr::current_timestamp())
+            .to_owned(),
+    )
+    .exec_with_returning(&txn)
+    .await
+    .context("Failed to add merged count to target source")?;
+
+    SourceHits::update_many()
+        .col_expr(source_hits::Column::SourceId, Expr::value(target.id))
+        .filter(source_hits::Column::SourceId.eq(source.id))
+        .exec(&txn)
+        .await
+        .context("Failed to move source hits")?;
+
+    // A visitor may already be counted on the target for the same day, those
+    // visitor-days are only added once
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        r"
+        WITH moved AS (
+            INSERT INTO source_visitors (source_id, day, visitor_hash)
+            SELECT $1, day, visitor_hash FROM source_visitors WHERE source_id = $2
+            ON CONFLICT DO NOTHING
+            RETURNING 1
+        )
+        UPDATE sources SET visitor_days = visitor_days + $3
+            - (SELECT COUNT(*) FROM source_visitors WHERE source_id = $2)
+            + (SELECT COUNT(*) FROM moved)
+        WHERE id = $1
+        ",
+        [
+            target.id.into(),
+            source.id.into(),
+            source.visitor_days.into(),
+        ],
+    ))
+    .await
+    .context("Failed to move source visitors")?;
+
+    SourceAliases::update_many()
+        .col_expr(source_aliases::Column::SourceId, Expr::value(target.id))
+        .filter(source_aliases::Column::SourceId.eq(source.id))
+        .exec(&txn)
+        .await
+        .context("Failed to move source aliases")?;
+
+    Sources::delete_by_id(source.id)
+        .exec(&txn)
+        .await
+        .context("Failed to delete merged source")?;
+
+    SourceAliases::insert(source_aliases::ActiveModel {
+        alias: Set(source.name.clone()),
+        source_id: Set(target.id),
+        ..Default::default()
+    })
+    .on_conflict(
+        OnConflict::column(source_aliases::Column::Alias)
+            .update_column(source_aliases::Column::SourceId)
+            .to_owned(),
+    )
+    .exec(&txn)
+    .await
+    .context("Failed to record source alias")?;
+
+    txn.commit()
+        .await
+        .context("Failed to commit source merge")?;
+
+    Ok(data_response(json!({
+        "from": source.name,
+        "into": target.name,
+        "merged": source.count,
+        "count": target.count,
+    })))
+}
+
+/// Fetches a source and locks its row until the transaction ends.
+///
+/// The name is first looked up as stored, so sources created before
+/// normalization was configured (e.g. `Twitter` or ` x.com`) can still be
+/// addressed, then in its normalized form.
+///
+/// # Errors
+/// Returns 404 if the source does not exist, 500 on database failure.
+async fn lock_source(
+    txn: &DatabaseTransaction,
+    normalization: &SourceNormalization,
+    name: &str,
+) -> ApiResult<sources::Model> {
+    let normalized = normalization.apply(name);
+    let candidates = if normalized == name {
+        vec![name]
+    } else {
+        vec![name, normalized.as_str()]
+    };
+
+    for candidate in candidates {
+        let source = Sources::find()
+            .filter(sources::Column::Name.eq(candidate))
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .context("Failed to fetch source")?;
+
+        if let Some(source) = source {
+            return Ok(source);
+        }
+    }
+
+    Err(ApiError::not_found(format!(
+        "Source '{}' not found",
+        validate::echo(&normalized)
+    )))
+}
+
+/// Handles PUT requests to a source path ("/source/:name").
+/// Renames a source and/or sets its counter.
+///
+/// The current name is looked up as stored, then normalized. The new name is
+/// normalized and validated. Renaming onto an existing source or alias is
+/// refused, merge the sources instead.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(normalization)` - The configured source normalization.
+/// * `Extension(validation)` - The configured source name rules.
+/// * `Path(name)` - The current source name.
+/// * `Json(payload)` - The new name and/or count.
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the updated source and its previous values.
+///
+/// # Errors
+/// * 400 if the payload is empty, the count is negative or the new name is refused or taken
+/// * 404 if the source does not exist
+/// * 500 on database failure
+pub async fn update_source(
     State(db): State<DatabaseConnection>,
-    Json(payload): Json<SourceRequest>,
+    Extension(normalization): Extension<SourceNormalization>,
+    Extension(validation): Extension<SourceValidation>,
+    Path(name): Path<String>,
+    Json(payload): Json<UpdateSourceRequest>,
 ) -> ApiResult<Json<serde_json::Value>> {
-    info!("POST `/source` endpoint called for: {}", payload.source);
+    info!("PUT `/source/{name}` endpoint called");
 
-    // Increment the source counter
-    crate::db::increment_source_in_db(&db, &payload.source)
+    if payload.name.is_none() && payload.count.is_none() {
+        return Err(ApiError::validation(
+            "Nothing to update, set `name` or `count`",
+        ));
+    }
+
+    if let Some(count) = payload.count
+        && count < 0
+    {
+        return Err(ApiError::validation(format!(
+            "count must not be negative, got {count}"
+        )));
+    }
+
+    let new_name = payload
+        .name
+        .as_deref()
+        .map(|new_name| normalization.apply(new_name));
+
+    let txn = db.begin().await.context("Failed to start transaction")?;
+    let source = lock_source(&txn, &normalization, &name).await?;
+
+    let mut update = sources::ActiveModel {
+        id: Unchanged(source.id),
+        updated_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    };
+
+    if let Some(new_name) = new_name.filter(|new_name| *new_name != source.name) {
+        validation.check(&new_name)?;
+
+        let taken = Sources::find()
+            .filter(sources::Column::Name.eq(&new_name))
+            .count(&txn)
+            .await
+            .context("Failed to check source name")?
+            > 0
+            || SourceAliases::find()
+                .filter(source_aliases::Column::Alias.eq(&new_name))
+                .count(&txn)
+                .await
+                .context("Failed to check source aliases")?
+                > 0;
+
+        if taken {
+            return Err(name_taken(&new_name));
+        }
+
+        update.name = Set(new_name);
+    }
+
+    if let Some(count) = payload.count {
+        update.count = Set(count);
+    }
+
+    let new_name = update.name.try_as_ref().cloned();
+    let updated = match update.update(&txn).await {
+        Ok(updated) => updated,
+        // A concurrent rename or first hit took the name after the check above
+        Err(e) if matches!(e.sql_err(), Some(SqlErr::UniqueConstraintViolation(_))) => {
+            return Err(name_taken(new_name.as_deref().unwrap_or(&source.name)));
+        }
+        Err(e) => {
+            return Err(anyhow::Error::new(e)
+                .context("Failed to update source")
+                .into());
+        }
+    };
+
+    txn.commit()
         .await
-        .context("Failed to increment source counter")?;
+        .context("Failed to commit source update")?;
+
+    Ok(data_response(json!({
+        "name": updated.name,
+        "count": updated.count,
+        "previous": {
+            "name": source.name,
+            "count": source.count,
+        },
+    })))
+}
+
+/// Refusal of a rename onto an existing source.
+fn name_taken(name: &str) -> ApiError {
+    ApiError::validation(format!(
+        "Source '{}' already exists, merge the sources instead",
+        validate::echo(name)
+    ))
+}
+
+/// Handles POST requests to a source reset path ("/source/:name/reset").
+/// Sets the counter, the bot counter and the uniques of a source back to zero.
+///
+/// The hit history, visitors and aliases are kept.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(normalization)` - The configured source normalization.
+/// * `Path(name)` - The source name.
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the source and its previous counts.
+///
+/// # Errors
+/// * 404 if the source does not exist
+/// * 500 on database failure
+pub async fn reset_source(
+    State(db): State<DatabaseConnection>,
+    Extension(normalization): Extension<SourceNormalization>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!("POST `/source/{name}/reset` endpoint called");
 
-    // Get the current count
-    let model = Sources::find()
-        .filter(sources::Column::Name.eq(&payload.source))
-        .one(&db)
+    let txn = db.begin().await.context("Failed to start transaction")?;
+    let source = lock_source(&txn, &normalization, &name).await?;
+
+    let updated = sources::ActiveModel {
+        id: Unchanged(source.id),
+        count: Set(0),
+        bot_count: Set(0),
+        visitor_days: Set(0),
+        updated_at: Set(chrono::Utc::now().naive_utc()),
+        ..Default::default()
+    }
+    .update(&txn)
+    .await
+    .context("Failed to reset source")?;
+
+    txn.commit()
+        .await
+        .context("Failed to commit source reset")?;
+
+    Ok(data_response(json!({
+        "name": updated.name,
+        "count": updated.count,
+        "previous": {
+            "count": source.count,
+            "bot_count": source.bot_count,
+            "uniques": source.visitor_days,
+        },
+    })))
+}
+
+/// Handles DELETE requests to a source path ("/source/:name").
+/// Deletes a source along with its hit history, visitors and aliases.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(normalization)` - The configured source normalization.
+/// * `Path(name)` - The source name.
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the deleted source and its last counts.
+///
+/// # Errors
+/// * 404 if the source does not exist
+/// * 500 on database failure
+pub async fn delete_source(
+    State(db): State<DatabaseConnection>,
+    Extension(normalization): Extension<SourceNormalization>,
+    Path(name): Path<String>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!("DELETE `/source/{name}` endpoint called");
+
+    let txn = db.begin().await.context("Failed to start transaction")?;
+    let source = lock_source(&txn, &normalization, &name).await?;
+
+    // Hits, visitors and aliases are removed by their `ON DELETE CASCADE` foreign keys
+    Sources::delete_by_id(source.id)
+        .exec(&txn)
         .await
-        .context("Failed to fetch updated source count")?
-        .context("Source not found after increment")?;
+        .context("Failed to delete source")?;
+
+    txn.commit()
+        .await
+        .context("Failed to commit source deletion")?;
 
     Ok(data_response(json!({
-        payload.source: model.count
+        "deleted": source.name,
+        "previous": {
+            "count": source.count,
+            "bot_count": source.bot_count,
+        },
     })))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_batch_entry_only_rejects_itself() {
+        let body = br#"[
+            { "source": "twitter", "count": 3 },
+            { "source": "mastodon", "count": "many" },
+            { "count": 2 },
+            { "source": "github", "utm_campaign": "launch" }
+        ]"#;
+
+        // The body as a whole is still accepted by the handler's extractor
+        let Json(values) = Json::<Vec<serde_json::Value>>::from_bytes(body).unwrap();
+        let (entries, errors) = parse_batch(values);
+
+        let parsed: Vec<_> = entries
+            .iter()
+            .map(|(index, entry)| (*index, entry.source.as_str(), entry.count))
+            .collect();
+        assert_eq!(parsed, [(0, "twitter", 3), (3, "github", 1)]);
+        assert_eq!(entries[1].1.utm.utm_campaign.as_deref(), Some("launch"));
+
+        let rejected: Vec<_> = errors
+            .iter()
+            .map(|error| (error.index, error.source.as_str()))
+            .collect();
+        assert_eq!(rejected, [(1, "mastodon"), (2, "")]);
+        assert!(
+            errors
+                .iter()
+                .all(|error| error.message.starts_with("invalid entry"))
+        );
+    }
+
+    #[test]
+    fn name_taken_quotes_a_truncated_name() {
+        let ApiError::ValidationFailed(message) = name_taken(&"x".repeat(200)) else {
+            panic!("expected a validation error");
+        };
+
+        assert!(
+            message.contains(&format!("'{}…'", "x".repeat(64))),
+            "{message}"
+        );
+        assert!(!message.contains(&"x".repeat(65)));
+    }
+}
@@ -8,10 +8,19 @@
 
 pub mod handlers;
 pub mod models;
+pub mod normalize;
+pub mod referrer;
+pub mod tracker;
+pub mod utm;
+pub mod validate;
+pub mod visitor;
 
-use handlers::{get_all_sources, increment_source};
+use handlers::{
+    delete_source, get_all_sources, increment_source, increment_sources_batch, merge_sources,
+    reset_source, update_source,
+};
 
-use axum::{Router, routing::get, routing::post};
+use axum::{Router, routing::get, routing::post, routing::put};
 use sea_orm::DatabaseConnection;
 
 /// Creates the source router with all endpoints
@@ -19,4 +28,8 @@ pub fn router() -> Router<DatabaseConnection> {
     Router::new()
         .route("/", get(get_all_sources))
         .route("/", post(increment_source))
+        .route("/batch", post(increment_sources_batch))
+        .route("/merge", post(merge_sources))
+        .route("/:name", put(update_source).delete(delete_source))
+        .route("/:name/reset", post(reset_source))
 }
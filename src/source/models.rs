@@ -2,10 +2,62 @@
 
 use serde::{Deserialize, Serialize};
 
+use super::utm::Utm;
+
 /// Request payload for incrementing a source counter
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct SourceRequest {
+    /// Source name, defaults to `utm_source`, then to the referrer, when omitted
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Landing URL whose `utm_*` query parameters fill the missing UTM fields
+    #[serde(default)]
+    pub landing_url: Option<String>,
+    #[serde(flatten)]
+    pub utm: Utm,
+}
+
+/// Request payload for merging one source into another
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MergeRequest {
+    /// Source to fold, becomes an alias of `into`
+    pub from: String,
+    /// Source receiving the count, created if missing
+    pub into: String,
+}
+
+/// Request payload for updating a source, every field is optional
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UpdateSourceRequest {
+    /// New name of the source
+    pub name: Option<String>,
+    /// New value of the counter
+    pub count: Option<i64>,
+}
+
+/// A single entry of a batch increment request
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BatchEntry {
     pub source: String,
+    #[serde(default = "default_batch_count")]
+    pub count: i64,
+    /// Landing URL whose `utm_*` query parameters fill the missing UTM fields
+    #[serde(default)]
+    pub landing_url: Option<String>,
+    #[serde(flatten)]
+    pub utm: Utm,
+}
+
+const fn default_batch_count() -> i64 {
+    1
+}
+
+/// An entry of a batch increment request that was rejected
+#[derive(Debug, Serialize)]
+pub struct BatchEntryError {
+    pub index: usize,
+    pub source: String,
+    pub message: String,
 }
 
 /// Response structure for source data
@@ -13,7 +65,141 @@ pub struct SourceRequest {
 pub struct SourceResponse {
     pub id: i64,
     pub name: String,
-    pub count: i32,
+    pub count: i64,
+    /// First seen, RFC 3339
     pub created_at: String,
+    /// Last human hit, RFC 3339, `None` without recorded hits
+    pub last_seen_at: Option<String>,
+    /// Last change of the row (hit, rename, reset or merge), RFC 3339
     pub updated_at: String,
 }
+
+/// Shape of the source listing
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceView {
+    /// Map of name to count
+    #[default]
+    Map,
+    /// Map of name to hits and uniques
+    Stats,
+    /// List of [`SourceResponse`] objects
+    Detailed,
+}
+
+impl SourceView {
+    /// The `view` query parameter value
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Map => "map",
+            Self::Stats => "stats",
+            Self::Detailed => "detailed",
+        }
+    }
+}
+
+/// Shape of the public source statistics
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceStatsView {
+    /// Map of name to count
+    #[default]
+    Map,
+    /// Map of name to hits and uniques
+    Stats,
+}
+
+/// Query parameters of the public source statistics
+#[derive(Debug, Default, Deserialize)]
+pub struct SourceStatsParams {
+    #[serde(default)]
+    pub view: SourceStatsView,
+}
+
+/// Query parameters of the source listing, besides pagination
+#[derive(Debug, Default, Deserialize)]
+pub struct SourceListParams {
+    #[serde(default)]
+    pub view: SourceView,
+}
+
+/// Bucket size for source time series
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Hour,
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// The Postgres `date_trunc` field name for this granularity
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+            Self::Week => "week",
+            Self::Month => "month",
+        }
+    }
+
+    /// Shortest possible bucket length in seconds, used to bound the number of buckets
+    #[must_use]
+    pub const fn min_seconds(self) -> i64 {
+        match self {
+            Self::Hour => 3_600,
+            Self::Day => 86_400,
+            Self::Week => 7 * 86_400,
+            Self::Month => 28 * 86_400,
+        }
+    }
+}
+
+/// Query parameters for the source time series endpoint
+#[derive(Debug, Deserialize)]
+pub struct TimeseriesParams {
+    /// Source name, all sources are aggregated when omitted
+    pub source: Option<String>,
+    /// Start of the range (inclusive), RFC 3339 or `YYYY-MM-DD`
+    pub from: Option<String>,
+    /// End of the range (exclusive), RFC 3339 or `YYYY-MM-DD`
+    pub to: Option<String>,
+    #[serde(default)]
+    pub granularity: Granularity,
+    /// Count bot hits as well
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+/// A single time series bucket
+#[derive(Debug, Serialize)]
+pub struct TimeseriesBucket {
+    pub bucket: String,
+    pub hits: i64,
+}
+
+/// Query parameters for the campaign statistics endpoint
+#[derive(Debug, Deserialize)]
+pub struct CampaignParams {
+    /// Only count hits of this source
+    pub source: Option<String>,
+    /// Start of the range (inclusive), RFC 3339 or `YYYY-MM-DD`
+    pub from: Option<String>,
+    /// End of the range (exclusive), RFC 3339 or `YYYY-MM-DD`
+    pub to: Option<String>,
+    /// Count bot hits as well
+    #[serde(default)]
+    pub include_bots: bool,
+}
+
+/// Hits of a campaign through one medium
+#[derive(Debug, Serialize)]
+pub struct CampaignStats {
+    pub campaign: String,
+    pub medium: Option<String>,
+    pub hits: i64,
+}
@@ -0,0 +1,114 @@
+//! Source name normalization
+//!
+//! Normalization runs before a source is counted so that variants such as
+//! `" Twitter"`, `"twitter"` and `"https://twitter.com/foo"` end up on the same
+//! counter. Which steps apply is configured through `SOURCE_NORMALIZATION`.
+
+/// Enabled normalization steps, applied in declaration order.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SourceNormalization {
+    /// Strip leading and trailing whitespace
+    pub trim: bool,
+    /// Replace URLs with their host, without a leading `www.`
+    pub extract_host: bool,
+    /// Fold the name to lowercase
+    pub lowercase: bool,
+}
+
+impl SourceNormalization {
+    /// Parse a comma-separated list of steps (`trim`, `host`, `lowercase`).
+    ///
+    /// # Errors
+    /// Returns an error naming the first unknown step.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let mut normalization = Self::default();
+
+        for step in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match step {
+                "trim" => normalization.trim = true,
+                "host" => normalization.extract_host = true,
+                "lowercase" => normalization.lowercase = true,
+                other => return Err(format!("Unknown source normalization step: {other}")),
+            }
+        }
+
+        Ok(normalization)
+    }
+
+    /// Apply the enabled steps to a source name.
+    #[must_use]
+    pub fn apply(&self, source: &str) -> String {
+        let mut name = if self.trim { source.trim() } else { source }.to_string();
+
+        if self.extract_host
+            && let Some(host) = url_host(&name)
+        {
+            name = host;
+        }
+
+        if self.lowercase {
+            name = name.to_lowercase();
+        }
+
+        name
+    }
+}
+
+/// Returns the host of an absolute URL, without a leading `www.`.
+fn url_host(value: &str) -> Option<String> {
+    let url = url::Url::parse(value).ok()?;
+    let host = url.host_str()?;
+    Some(host.strip_prefix("www.").unwrap_or(host).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_steps_fold_a_url_to_its_host() {
+        let normalization = SourceNormalization::from_spec("trim,host,lowercase").unwrap();
+
+        assert_eq!(
+            normalization.apply(" https://www.Twitter.com/x"),
+            "twitter.com"
+        );
+    }
+
+    #[test]
+    fn steps_apply_only_when_enabled() {
+        let trim = SourceNormalization::from_spec("trim").unwrap();
+        assert_eq!(trim.apply("  Twitter \n"), "Twitter");
+
+        let lowercase = SourceNormalization::from_spec("lowercase").unwrap();
+        assert_eq!(lowercase.apply(" Twitter"), " twitter");
+
+        let none = SourceNormalization::default();
+        assert_eq!(none.apply(" https://www.X.com/"), " https://www.X.com/");
+    }
+
+    #[test]
+    fn host_keeps_names_that_are_not_urls() {
+        let host = SourceNormalization::from_spec("host").unwrap();
+
+        assert_eq!(
+            host.apply("https://news.ycombinator.com/item"),
+            "news.ycombinator.com"
+        );
+        assert_eq!(host.apply("http://www.example.org"), "example.org");
+        assert_eq!(host.apply("newsletter"), "newsletter");
+        assert_eq!(host.apply("twitter.com/x"), "twitter.com/x");
+    }
+
+    #[test]
+    fn spec_ignores_blanks_and_rejects_unknown_steps() {
+        let normalization = SourceNormalization::from_spec(" trim , ,lowercase,").unwrap();
+        assert!(normalization.trim && normalization.lowercase && !normalization.extract_host);
+
+        assert!(SourceNormalization::from_spec("").is_ok());
+        assert_eq!(
+            SourceNormalization::from_spec("trim,upper").unwrap_err(),
+            "Unknown source normalization step: upper"
+        );
+    }
+}
@@ -0,0 +1,165 @@
+//! Source detection from the request referrer
+//!
+//! When a hit does not name its source, the host of the `Referer` (or
+//! `Origin`) header is used instead, mapped through `REFERRER_SOURCES` so that
+//! e.g. `t.co` and `x.com` both count as `twitter`.
+
+use axum::http::{HeaderMap, header};
+use std::sync::Arc;
+
+/// Source used when the request carries no referrer
+pub const DIRECT_SOURCE: &str = "direct";
+
+/// Mapping of referrer hosts to source names.
+///
+/// A host matches an entry when it equals the entry's host or is a subdomain of it.
+#[derive(Debug, Clone, Default)]
+pub struct ReferrerSources {
+    hosts: Arc<Vec<(String, String)>>,
+}
+
+impl ReferrerSources {
+    /// Parse a comma-separated list of `host=source` pairs.
+    ///
+    /// # Errors
+    /// Returns an error naming the first malformed pair.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let hosts = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((host, source)) if !host.trim().is_empty() && !source.trim().is_empty() => {
+                    Ok((
+                        strip_www(&host.trim().to_lowercase()),
+                        source.trim().to_string(),
+                    ))
+                }
+                _ => Err(format!(
+                    "Invalid referrer mapping, expected host=source: {pair}"
+                )),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            hosts: Arc::new(hosts),
+        })
+    }
+
+    /// Derive the source of a request from its `Referer` or `Origin` header.
+    ///
+    /// # Returns
+    /// The mapped source of the referrer host, the host itself when it is not
+    /// mapped, or [`DIRECT_SOURCE`] without a usable referrer.
+    #[must_use]
+    pub fn source(&self, headers: &HeaderMap) -> String {
+        let Some(host) = referrer_host(headers) else {
+            return DIRECT_SOURCE.to_string();
+        };
+
+        self.hosts
+            .iter()
+            .find(|(mapped, _)| {
+                host == *mapped
+                    || host
+                        .strip_suffix(mapped.as_str())
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            })
+            .map_or(host.clone(), |(_, source)| source.clone())
+    }
+}
+
+/// Returns the lowercase host of the `Referer` header, falling back to `Origin`.
+fn referrer_host(headers: &HeaderMap) -> Option<String> {
+    [header::REFERER, header::ORIGIN]
+        .iter()
+        .filter_map(|name| headers.get(name)?.to_str().ok())
+        .filter_map(|value| url::Url::parse(value).ok())
+        .find_map(|url| url.host_str().map(|host| strip_www(&host.to_lowercase())))
+}
+
+fn strip_www(host: &str) -> String {
+    host.strip_prefix("www.").unwrap_or(host).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(header::HeaderName, &'static str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| (name.clone(), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    fn referrers() -> ReferrerSources {
+        ReferrerSources::from_spec("t.co=twitter, www.X.com=twitter,ycombinator.com=hn").unwrap()
+    }
+
+    #[test]
+    fn referrer_host_is_lowercased_without_www() {
+        let headers = headers(&[(header::REFERER, "https://WWW.Example.org/post?id=1")]);
+
+        assert_eq!(referrer_host(&headers).as_deref(), Some("example.org"));
+    }
+
+    #[test]
+    fn mapped_hosts_and_their_subdomains_give_the_source() {
+        let referrers = referrers();
+
+        for referer in [
+            "https://t.co/abc",
+            "https://x.com/someone",
+            "https://mobile.x.com/someone",
+        ] {
+            let headers = headers(&[(header::REFERER, referer)]);
+            assert_eq!(referrers.source(&headers), "twitter", "{referer}");
+        }
+
+        let headers = headers(&[(header::REFERER, "https://news.ycombinator.com/item")]);
+        assert_eq!(referrers.source(&headers), "hn");
+    }
+
+    #[test]
+    fn unmapped_hosts_are_kept_as_source() {
+        let referrers = referrers();
+
+        // A suffix that is not a subdomain must not match
+        let headers = headers(&[(header::REFERER, "https://notx.com/")]);
+        assert_eq!(referrers.source(&headers), "notx.com");
+    }
+
+    #[test]
+    fn origin_is_used_without_a_usable_referer() {
+        let referrers = referrers();
+
+        let origin_only = headers(&[(header::ORIGIN, "https://t.co")]);
+        assert_eq!(referrers.source(&origin_only), "twitter");
+
+        let invalid_referer = headers(&[
+            (header::REFERER, "not a url"),
+            (header::ORIGIN, "https://github.com"),
+        ]);
+        assert_eq!(referrers.source(&invalid_referer), "github.com");
+    }
+
+    #[test]
+    fn requests_without_referrer_are_direct() {
+        let referrers = referrers();
+
+        assert_eq!(referrers.source(&HeaderMap::new()), DIRECT_SOURCE);
+        assert_eq!(
+            referrers.source(&headers(&[(header::REFERER, "android-app://")])),
+            DIRECT_SOURCE
+        );
+    }
+
+    #[test]
+    fn malformed_mappings_are_refused() {
+        assert!(ReferrerSources::from_spec("").is_ok());
+        assert!(ReferrerSources::from_spec("t.co").is_err());
+        assert!(ReferrerSources::from_spec("t.co=, x.com=twitter").is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Source hit recording
+//!
+//! The [`SourceTracker`] is the single increment path shared by the
+//! authenticated source endpoint and the public tracking routes: it
+//! normalizes and validates the name, identifies the visitor, classifies
+//! bots and increments the counter.
+
+use anyhow::Context;
+use axum::http::HeaderMap;
+use serde_json::json;
+use std::net::SocketAddr;
+
+use super::{
+    normalize::SourceNormalization, utm::Utm, validate::SourceValidation, visitor::VisitorHasher,
+};
+use crate::{
+    bot::BotFilter,
+    counter::{Counters, HitDetails},
+    error::ApiResult,
+};
+
+/// Records source hits.
+#[derive(Debug, Clone)]
+pub struct SourceTracker {
+    counters: Counters,
+    normalization: SourceNormalization,
+    validation: SourceValidation,
+    hasher: VisitorHasher,
+    bots: BotFilter,
+}
+
+impl SourceTracker {
+    /// Create a tracker from its parts.
+    #[must_use]
+    pub const fn new(
+        counters: Counters,
+        normalization: SourceNormalization,
+        validation: SourceValidation,
+        hasher: VisitorHasher,
+        bots: BotFilter,
+    ) -> Self {
+        Self {
+            counters,
+            normalization,
+            validation,
+            hasher,
+            bots,
+        }
+    }
+
+    /// Normalizes and validates a source name and increments its counter, recording the UTM
+    /// parameters on the hit and the visitor for unique counts. Bot hits go to
+    /// the separate bot counter.
+    ///
+    /// # Arguments
+    /// * `raw_source` - The source name as received.
+    /// * `utm` - UTM parameters of the hit.
+    /// * `headers` - The request headers, used to identify the visitor and bots.
+    /// * `peer` - The client socket address, when available.
+    ///
+    /// # Returns
+    /// A JSON object mapping the canonical source name to its new count, or the
+    /// normalized name to `null` when counters are buffered.
+    ///
+    /// # Errors
+    /// Returns 400 if the name is refused, 500 if the database operation fails.
+    pub async fn record_hit(
+        &self,
+        raw_source: &str,
+        utm: &Utm,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+    ) -> ApiResult<serde_json::Value> {
+        let source = self.normalization.apply(raw_source);
+        self.validation
+            .validate(self.counters.db(), &source)
+            .await?;
+        let hit = HitDetails {
+            utm: utm.clone(),
+            visitor: self.hasher.visitor(headers, peer),
+            bot: self.bots.is_bot(headers, peer),
+            at: None,
+        };
+
+        // Increment the source counter, the upsert returns the new count
+        let updated = self
+            .counters
+            .increment_source(&source, 1, &hit)
+            .await
+            .context("Failed to increment source counter")?;
+
+        Ok(match updated {
+            Some(updated) => json!({ updated.name: updated.count }),
+            None => json!({ source: null }),
+        })
+    }
+}
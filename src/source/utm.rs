@@ -0,0 +1,130 @@
+//! UTM campaign parameters
+//!
+//! Hits can carry the five standard `utm_*` parameters, either posted
+//! explicitly or parsed from the landing URL. They are stored as separate
+//! dimensions on each `source_hits` event.
+
+use serde::{Deserialize, Serialize};
+
+/// UTM parameters of a hit, all optional
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub struct Utm {
+    pub utm_source: Option<String>,
+    pub utm_medium: Option<String>,
+    pub utm_campaign: Option<String>,
+    pub utm_term: Option<String>,
+    pub utm_content: Option<String>,
+}
+
+impl Utm {
+    /// Parse the `utm_*` query parameters of a landing URL.
+    ///
+    /// Returns empty parameters if the URL cannot be parsed.
+    #[must_use]
+    pub fn from_url(landing_url: &str) -> Self {
+        let mut utm = Self::default();
+
+        let Ok(url) = url::Url::parse(landing_url) else {
+            return utm;
+        };
+
+        for (key, value) in url.query_pairs() {
+            let slot = match key.as_ref() {
+                "utm_source" => &mut utm.utm_source,
+                "utm_medium" => &mut utm.utm_medium,
+                "utm_campaign" => &mut utm.utm_campaign,
+                "utm_term" => &mut utm.utm_term,
+                "utm_content" => &mut utm.utm_content,
+                _ => continue,
+            };
+            *slot = Some(value.into_owned());
+        }
+
+        utm
+    }
+
+    /// Fill parameters missing from `self` with those of `fallback`,
+    /// then drop empty values.
+    #[must_use]
+    pub fn or(self, fallback: Self) -> Self {
+        let pick = |value: Option<String>, fallback: Option<String>| {
+            value
+                .or(fallback)
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+
+        Self {
+            utm_source: pick(self.utm_source, fallback.utm_source),
+            utm_medium: pick(self.utm_medium, fallback.utm_medium),
+            utm_campaign: pick(self.utm_campaign, fallback.utm_campaign),
+            utm_term: pick(self.utm_term, fallback.utm_term),
+            utm_content: pick(self.utm_content, fallback.utm_content),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn some(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn utm_parameters_are_read_from_the_landing_url() {
+        let utm = Utm::from_url(
+            "https://example.com/post?utm_source=twitter&utm_medium=social&utm_campaign=spring%20launch&utm_term=rust+axum&utm_content=banner&ref=home",
+        );
+
+        assert_eq!(
+            utm,
+            Utm {
+                utm_source: some("twitter"),
+                utm_medium: some("social"),
+                utm_campaign: some("spring launch"),
+                utm_term: some("rust axum"),
+                utm_content: some("banner"),
+            }
+        );
+    }
+
+    #[test]
+    fn landing_url_without_utm_gives_no_parameters() {
+        assert_eq!(
+            Utm::from_url("https://example.com/post?ref=home#utm_source=x"),
+            Utm::default()
+        );
+    }
+
+    #[test]
+    fn malformed_landing_url_gives_no_parameters() {
+        assert_eq!(Utm::from_url("not a url?utm_source=x"), Utm::default());
+        assert_eq!(Utm::from_url("/post?utm_source=x"), Utm::default());
+        assert_eq!(Utm::from_url(""), Utm::default());
+    }
+
+    #[test]
+    fn body_fields_win_over_url_fields() {
+        let body = Utm {
+            utm_source: some("newsletter"),
+            utm_campaign: some("  october  "),
+            ..Utm::default()
+        };
+        let url = Utm::from_url(
+            "https://example.com/?utm_source=twitter&utm_medium=email&utm_campaign=spring&utm_term=%20",
+        );
+
+        assert_eq!(
+            body.or(url),
+            Utm {
+                utm_source: some("newsletter"),
+                utm_medium: some("email"),
+                utm_campaign: some("october"),
+                utm_term: None,
+                utm_content: None,
+            }
+        );
+    }
+}
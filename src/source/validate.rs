@@ -0,0 +1,366 @@
+//! Source name validation
+//!
+//! Every new source name becomes a permanent row, so names are checked after
+//! normalization: length, an optional pattern, an optional allow-list and,
+//! in `reject` mode, whether the source already exists. Aliases are resolved
+//! first, so the pattern and allow-list apply to the source a hit is counted
+//! on. Rules are configured through the `SOURCE_*` variables.
+//!
+//! Refused names are echoed in error messages truncated to
+//! [`MAX_ECHOED_LENGTH`] characters.
+
+use anyhow::Context;
+use regex::Regex;
+use sea_orm::{ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use crate::{
+    entities::{prelude::*, source_aliases, sources},
+    error::{ApiError, ApiResult},
+};
+
+/// Longest part of a refused name quoted in error messages
+pub const MAX_ECHOED_LENGTH: usize = 64;
+
+/// What happens to names that do not match an existing source.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnknownSources {
+    /// Create the source on its first hit
+    #[default]
+    Create,
+    /// Refuse the hit, sources must exist or be allow-listed
+    Reject,
+}
+
+/// Validation rules applied to source names.
+#[derive(Debug, Clone)]
+pub struct SourceValidation {
+    /// Maximum length in characters
+    pub max_length: usize,
+    /// Pattern the whole name must match
+    pub pattern: Option<Regex>,
+    /// Only these names are accepted, when set
+    pub allowlist: Option<Arc<HashSet<String>>>,
+    /// Handling of names without a source row
+    pub unknown: UnknownSources,
+}
+
+/// Aliases and sources matching a set of names, looked up once so a whole
+/// batch is validated without a query per name.
+#[derive(Debug, Default)]
+pub struct KnownSources {
+    /// Alias to the name of the source it points to
+    aliases: HashMap<String, String>,
+    /// Names of existing sources
+    sources: HashSet<String>,
+}
+
+impl Default for SourceValidation {
+    fn default() -> Self {
+        Self {
+            max_length: 128,
+            pattern: None,
+            allowlist: None,
+            unknown: UnknownSources::Create,
+        }
+    }
+}
+
+impl SourceValidation {
+    /// Check a normalized name against the static rules.
+    ///
+    /// # Errors
+    /// Returns a validation error with the reason the name is refused.
+    pub fn check(&self, name: &str) -> ApiResult<()> {
+        self.check_format(name)?;
+        self.check_rules(name)
+    }
+
+    /// Check a normalized name before counting a hit on it.
+    ///
+    /// Aliases are resolved to their source first, and [`Self::check`] applies
+    /// to that source. In addition, `reject` mode refuses names that are
+    /// neither an existing source, an alias, nor allow-listed.
+    ///
+    /// # Errors
+    /// Returns a validation error with the reason the name is refused, or 500
+    /// on database failure.
+    pub async fn validate<C: ConnectionTrait>(&self, db: &C, name: &str) -> ApiResult<()> {
+        self.check_format(name)?;
+        let known = self.lookup(db, [name]).await?;
+        self.validate_known(&known, name)
+    }
+
+    /// Look up the aliases and sources [`Self::validate_known`] needs for
+    /// the given names, in two queries whatever their number.
+    ///
+    /// Nothing is fetched when no rule depends on existing sources.
+    ///
+    /// # Errors
+    /// Returns 500 on database failure.
+    pub async fn lookup<'a, C: ConnectionTrait>(
+        &self,
+        db: &C,
+        names: impl IntoIterator<Item = &'a str>,
+    ) -> ApiResult<KnownSources> {
+        if !self.needs_lookup() {
+            return Ok(KnownSources::default());
+        }
+
+        let names: Vec<&str> = names.into_iter().collect();
+
+        let aliases = SourceAliases::find()
+            .filter(source_aliases::Column::Alias.is_in(names.iter().copied()))
+            .all(db)
+            .await
+            .context("Failed to look up source aliases")?;
+
+        let rows = Sources::find()
+            .filter(
+                Condition::any()
+                    .add(sources::Column::Name.is_in(names.iter().copied()))
+                    .add(sources::Column::Id.is_in(aliases.iter().map(|alias| alias.source_id))),
+            )
+            .all(db)
+            .await
+            .context("Failed to look up sources")?;
+
+        let names_by_id: HashMap<i32, &str> = rows
+            .iter()
+            .map(|source| (source.id, source.name.as_str()))
+            .collect();
+
+        Ok(KnownSources {
+            aliases: aliases
+                .iter()
+                .filter_map(|alias| {
+                    let source = names_by_id.get(&alias.source_id)?;
+                    Some((alias.alias.clone(), (*source).to_string()))
+                })
+                .collect(),
+            sources: rows.into_iter().map(|source| source.name).collect(),
+        })
+    }
+
+    /// Check a normalized name before counting a hit on it, against sources
+    /// and aliases fetched by [`Self::lookup`].
+    ///
+    /// Same rules as [`Self::validate`].
+    ///
+    /// # Errors
+    /// Returns a validation error with the reason the name is refused.
+    pub fn validate_known(&self, known: &KnownSources, name: &str) -> ApiResult<()> {
+        self.check_format(name)?;
+
+        // Without pattern, allow-list or reject mode, aliases do not matter
+        if !self.needs_lookup() {
+            return Ok(());
+        }
+
+        // The alias exists, only the source it points to has to pass the rules
+        if let Some(source) = known.aliases.get(name) {
+            return self.check_rules(source);
+        }
+
+        self.check_rules(name)?;
+
+        if self.unknown == UnknownSources::Create || self.allowlist.is_some() {
+            return Ok(());
+        }
+
+        if !known.sources.contains(name) {
+            return Err(ApiError::validation(format!(
+                "unknown source '{}', new sources are not accepted",
+                echo(name)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Whether a rule depends on the existing sources and aliases.
+    fn needs_lookup(&self) -> bool {
+        self.pattern.is_some() || self.allowlist.is_some() || self.unknown == UnknownSources::Reject
+    }
+
+    /// Check the length and characters of a name.
+    fn check_format(&self, name: &str) -> ApiResult<()> {
+        if name.trim().is_empty() {
+            return Err(ApiError::validation("source must not be empty"));
+        }
+
+        if name.chars().count() > self.max_length {
+            return Err(ApiError::validation(format!(
+                "source '{}' is longer than {} characters",
+                echo(name),
+                self.max_length
+            )));
+        }
+
+        if name.chars().any(char::is_control) {
+            return Err(ApiError::validation(format!(
+                "source '{}' contains control characters",
+                echo(name)
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Check a name against the pattern and the allow-list.
+    fn check_rules(&self, name: &str) -> ApiResult<()> {
+        if let Some(pattern) = &self.pattern
+            && !pattern.is_match(name)
+        {
+            return Err(ApiError::validation(format!(
+                "source '{}' does not match the allowed pattern `{pattern}`",
+                echo(name)
+            )));
+        }
+
+        if let Some(allowlist) = &self.allowlist
+            && !allowlist.contains(name)
+        {
+            return Err(ApiError::validation(format!(
+                "source '{}' is not in the allow-list",
+                echo(name)
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Compile a name pattern, anchored so it applies to the whole name.
+///
+/// # Errors
+/// Returns the regex error if the pattern is invalid.
+pub fn whole_name_pattern(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{pattern})$"))
+}
+
+/// Quote a name in an error message, escaped and truncated to
+/// [`MAX_ECHOED_LENGTH`] characters.
+#[must_use]
+pub fn echo(name: &str) -> String {
+    let mut echoed: String = name
+        .chars()
+        .take(MAX_ECHOED_LENGTH)
+        .flat_map(char::escape_debug)
+        .collect();
+
+    if name.chars().count() > MAX_ECHOED_LENGTH {
+        echoed.push('…');
+    }
+
+    echoed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known(aliases: &[(&str, &str)], sources: &[&str]) -> KnownSources {
+        KnownSources {
+            aliases: aliases
+                .iter()
+                .map(|(alias, source)| ((*alias).to_string(), (*source).to_string()))
+                .collect(),
+            sources: sources.iter().map(|name| (*name).to_string()).collect(),
+        }
+    }
+
+    fn refusal(result: ApiResult<()>) -> String {
+        match result {
+            Err(ApiError::ValidationFailed(reason)) => reason,
+            other => panic!("expected a validation error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn length_is_counted_in_characters() {
+        let validation = SourceValidation {
+            max_length: 4,
+            ..SourceValidation::default()
+        };
+
+        assert!(validation.check("héhé").is_ok());
+        assert!(refusal(validation.check("twitter")).contains("longer than 4 characters"));
+        assert!(refusal(validation.check("   ")).contains("must not be empty"));
+    }
+
+    #[test]
+    fn control_characters_are_refused() {
+        let validation = SourceValidation::default();
+
+        let reason = refusal(validation.check("twit\nter"));
+        assert!(reason.contains("control characters"));
+        assert!(reason.contains("twit\\nter"));
+    }
+
+    #[test]
+    fn pattern_must_match_the_whole_name() {
+        let validation = SourceValidation {
+            pattern: Some(whole_name_pattern("[a-z]+|x\\.com").unwrap()),
+            ..SourceValidation::default()
+        };
+
+        assert!(validation.check("twitter").is_ok());
+        assert!(validation.check("x.com").is_ok());
+        assert!(validation.check("twitter!").is_err());
+        assert!(validation.check("1x.com").is_err());
+    }
+
+    #[test]
+    fn allowlist_applies_to_the_aliased_source() {
+        let validation = SourceValidation {
+            allowlist: Some(Arc::new(HashSet::from(["twitter".to_string()]))),
+            ..SourceValidation::default()
+        };
+        let known = known(&[("x.com", "twitter"), ("bsky", "bluesky")], &[]);
+
+        assert!(validation.validate_known(&known, "twitter").is_ok());
+        assert!(validation.validate_known(&known, "x.com").is_ok());
+        assert!(refusal(validation.validate_known(&known, "bsky")).contains("'bluesky'"));
+        assert!(refusal(validation.check("github")).contains("allow-list"));
+    }
+
+    #[test]
+    fn reject_mode_only_accepts_known_names() {
+        let validation = SourceValidation {
+            unknown: UnknownSources::Reject,
+            ..SourceValidation::default()
+        };
+        let known = known(&[("x.com", "twitter")], &["twitter"]);
+
+        assert!(validation.validate_known(&known, "twitter").is_ok());
+        assert!(validation.validate_known(&known, "x.com").is_ok());
+        assert!(refusal(validation.validate_known(&known, "github")).contains("unknown source"));
+    }
+
+    #[test]
+    fn create_mode_accepts_new_names() {
+        let validation = SourceValidation::default();
+
+        assert!(
+            validation
+                .validate_known(&KnownSources::default(), "github")
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn echo_truncates_long_names() {
+        let name = "a".repeat(MAX_ECHOED_LENGTH + 10);
+
+        let echoed = echo(&name);
+
+        assert_eq!(echoed.chars().count(), MAX_ECHOED_LENGTH + 1);
+        assert!(echoed.ends_with('…'));
+        assert_eq!(echo("twitter"), "twitter");
+        assert_eq!(echo("a\tb"), "a\\tb");
+    }
+}
@@ -0,0 +1,193 @@
+//! Privacy-preserving visitor identification
+//!
+//! A visitor is identified by `SHA-256(salt || client IP || User-Agent)`.
+//! The salt is random, kept in memory only and replaced every UTC day, so a
+//! hash cannot be linked to an IP address or to the same visitor on another day.
+//! A restart draws a new salt, so visitors seen earlier that day count again.
+
+use axum::http::{HeaderMap, header};
+use chrono::NaiveDate;
+use sha2::{Digest, Sha256};
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use crate::client::client_ip;
+
+/// A hashed visitor for a given day
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Visitor {
+    pub day: NaiveDate,
+    pub hash: String,
+}
+
+/// Salt of the current day
+#[derive(Debug)]
+struct DailySalt {
+    day: NaiveDate,
+    salt: [u8; 32],
+}
+
+/// Hashes client IP and User-Agent into daily visitor ids.
+#[derive(Debug, Clone)]
+pub struct VisitorHasher {
+    salt: Arc<Mutex<DailySalt>>,
+    trust_proxy_headers: bool,
+}
+
+impl VisitorHasher {
+    /// Create a hasher with a fresh salt.
+    ///
+    /// # Arguments
+    /// * `trust_proxy_headers` - Read the client IP from `X-Forwarded-For` / `X-Real-IP`,
+    ///   only safe behind a reverse proxy that sets them.
+    #[must_use]
+    pub fn new(trust_proxy_headers: bool) -> Self {
+        Self {
+            salt: Arc::new(Mutex::new(DailySalt {
+                day: chrono::Utc::now().date_naive(),
+                salt: rand::random(),
+            })),
+            trust_proxy_headers,
+        }
+    }
+
+    /// Identify the visitor behind a request.
+    ///
+    /// # Returns
+    /// `None` if the client IP cannot be determined.
+    #[must_use]
+    pub fn visitor(&self, headers: &HeaderMap, peer: Option<SocketAddr>) -> Option<Visitor> {
+        self.visitor_on(chrono::Utc::now().date_naive(), headers, peer)
+    }
+
+    /// Identify the visitor behind a request on a given UTC day.
+    fn visitor_on(
+        &self,
+        today: NaiveDate,
+        headers: &HeaderMap,
+        peer: Option<SocketAddr>,
+    ) -> Option<Visitor> {
+        let ip = client_ip(headers, peer, self.trust_proxy_headers)?;
+        let user_agent = headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default();
+
+        let (day, salt) = self.current_salt(today);
+
+        let mut hasher = Sha256::new();
+        hasher.update(salt);
+        hasher.update(ip.to_string().as_bytes());
+        hasher.update([0]);
+        hasher.update(user_agent.as_bytes());
+
+        Some(Visitor {
+            day,
+            hash: hex::encode(hasher.finalize()),
+        })
+    }
+
+    /// Return today's salt, replacing it when the UTC day changed.
+    fn current_salt(&self, today: NaiveDate) -> (NaiveDate, [u8; 32]) {
+        let mut current = self.salt.lock().expect("visitor salt lock poisoned");
+
+        if current.day != today {
+            *current = DailySalt {
+                day: today,
+                salt: rand::random(),
+            };
+        }
+
+        (current.day, current.salt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    fn user_agent(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::USER_AGENT, HeaderValue::from_static(value));
+        headers
+    }
+
+    fn peer(ip: &str) -> Option<SocketAddr> {
+        Some(SocketAddr::new(ip.parse().unwrap(), 443))
+    }
+
+    #[test]
+    fn same_visitor_has_the_same_hash_all_day() {
+        let hasher = VisitorHasher::new(false);
+        let browser = user_agent("Mozilla/5.0");
+
+        let first = hasher.visitor_on(day(16), &browser, peer("203.0.113.7"));
+        let again = hasher.visitor_on(day(16), &browser, peer("203.0.113.7"));
+
+        assert_eq!(first, again);
+        let first = first.unwrap();
+        assert_eq!(first.day, day(16));
+        assert_eq!(first.hash.len(), 64);
+        assert!(first.hash.bytes().all(|b| b.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn ip_and_user_agent_both_identify_the_visitor() {
+        let hasher = VisitorHasher::new(false);
+        let hash = |agent, ip| {
+            hasher
+                .visitor_on(day(16), &user_agent(agent), peer(ip))
+                .unwrap()
+                .hash
+        };
+
+        let visitor = hash("Mozilla/5.0", "203.0.113.7");
+        assert_ne!(visitor, hash("Mozilla/5.0", "203.0.113.8"));
+        assert_ne!(visitor, hash("curl/8.0", "203.0.113.7"));
+        assert!(
+            hasher
+                .visitor_on(day(16), &HeaderMap::new(), None)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn salt_rotates_with_the_day() {
+        let hasher = VisitorHasher::new(false);
+        let browser = user_agent("Mozilla/5.0");
+
+        let monday = hasher
+            .visitor_on(day(12), &browser, peer("203.0.113.7"))
+            .unwrap();
+        let tuesday = hasher
+            .visitor_on(day(13), &browser, peer("203.0.113.7"))
+            .unwrap();
+
+        assert_eq!(tuesday.day, day(13));
+        assert_ne!(monday.hash, tuesday.hash);
+        assert_eq!(
+            hasher.visitor_on(day(13), &browser, peer("203.0.113.7")),
+            Some(tuesday)
+        );
+    }
+
+    #[test]
+    fn separate_hashers_do_not_share_salts() {
+        let browser = user_agent("Mozilla/5.0");
+        let visitor =
+            |hasher: &VisitorHasher| hasher.visitor_on(day(16), &browser, peer("203.0.113.7"));
+
+        // As after a restart: the same visitor gets a new hash
+        assert_ne!(
+            visitor(&VisitorHasher::new(false)),
+            visitor(&VisitorHasher::new(false))
+        );
+    }
+}
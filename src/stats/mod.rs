@@ -2,12 +2,16 @@ use axum::{Router, routing::get};
 use sea_orm::DatabaseConnection;
 
 use crate::brew::handlers::get_brew_stats;
+use crate::counter::handlers::get_counter_stats;
 use crate::github_stats::handlers::get_github_stats;
-use crate::source::handlers::get_source_stats;
+use crate::source::handlers::{get_campaign_stats, get_source_stats, get_source_timeseries};
 
 pub fn router() -> Router<DatabaseConnection> {
     Router::new()
         .route("/github", get(get_github_stats))
         .route("/brew", get(get_brew_stats))
         .route("/sources", get(get_source_stats))
+        .route("/sources/timeseries", get(get_source_timeseries))
+        .route("/campaigns", get(get_campaign_stats))
+        .route("/counters", get(get_counter_stats))
 }
@@ -0,0 +1,327 @@
+//! Server-side clustering of public stickers
+//!
+//! Stickers are grouped on a grid of [`CELL_PIXELS`]-pixel squares in Web
+//! Mercator tile space, so cells have the same on-screen size at every zoom
+//! level, as map clustering libraries do. Each cluster reports the centroid
+//! of its stickers, their count and a few of their ids.
+//!
+//! Only public stickers are clustered, on their fuzzed coordinates, so
+//! clusters reveal nothing the public feed does not.
+//!
+//! The clusters of the whole world are computed once per zoom level and
+//! cached until a sticker changes; a bounding box only filters the cached
+//! clusters, keeping those whose grid cell overlaps it. Concurrent requests
+//! missing the same level wait for a single computation.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use serde::Serialize;
+use tokio::sync::{Mutex as AsyncMutex, MutexGuard as AsyncMutexGuard};
+
+use super::geo::GeoBox;
+
+/// Highest zoom level clusters are computed for
+pub const MAX_ZOOM: u8 = 20;
+
+/// Width of a grid cell, in pixels of 256-pixel tiles
+const CELL_PIXELS: f64 = 64.0;
+
+/// Number of sticker ids reported per cluster
+const SAMPLE_SIZE: usize = 5;
+
+/// Web Mercator latitude limit
+const MAX_MERCATOR_LAT: f64 = 85.051_128_78;
+
+/// A group of nearby stickers
+#[derive(Debug, Clone, Serialize)]
+pub struct Cluster {
+    /// Mean latitude of the stickers
+    pub latitude: f64,
+    /// Mean longitude of the stickers
+    pub longitude: f64,
+    pub count: usize,
+    /// Ids of the newest stickers of the cluster
+    pub sample_ids: Vec<i32>,
+    /// Bounds of the grid cell holding the stickers
+    #[serde(skip)]
+    pub cell: GeoBox,
+}
+
+/// A sticker to cluster, with its public coordinates
+#[derive(Debug, Clone, Copy)]
+pub struct Point {
+    pub id: i32,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Clusters of every zoom level computed so far.
+///
+/// Cloning is cheap, clones share the cache.
+#[derive(Debug, Clone)]
+pub struct ClusterCache {
+    levels: Arc<Mutex<HashMap<u8, Arc<Vec<Cluster>>>>>,
+    generation: Arc<AtomicU64>,
+    /// Held while a level is computed, one per zoom level
+    computing: Arc<[AsyncMutex<()>]>,
+}
+
+impl Default for ClusterCache {
+    fn default() -> Self {
+        Self {
+            levels: Arc::default(),
+            generation: Arc::default(),
+            computing: (0..=MAX_ZOOM).map(|_| AsyncMutex::new(())).collect(),
+        }
+    }
+}
+
+impl ClusterCache {
+    /// Cached clusters of a zoom level.
+    #[must_use]
+    pub fn get(&self, zoom: u8) -> Option<Arc<Vec<Cluster>>> {
+        self.lock().get(&zoom).cloned()
+    }
+
+    /// Wait for the right to compute a zoom level.
+    ///
+    /// Hold the guard while loading and inserting the level, then check the
+    /// cache again after acquiring it: another request may have filled it.
+    pub async fn computing(&self, zoom: u8) -> AsyncMutexGuard<'_, ()> {
+        self.computing[usize::from(zoom.min(MAX_ZOOM))].lock().await
+    }
+
+    /// Current cache generation, read before loading the stickers to cluster.
+    #[must_use]
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Cache the clusters of a zoom level, unless stickers changed since
+    /// `generation` was read.
+    pub fn insert(&self, zoom: u8, generation: u64, clusters: Arc<Vec<Cluster>>) {
+        let mut levels = self.lock();
+        // Checked under the lock, which `invalidate` also takes
+        if self.generation() == generation {
+            levels.insert(zoom, clusters);
+        }
+    }
+
+    /// Drop every cached level, after a sticker was created, changed or deleted.
+    pub fn invalidate(&self) {
+        let mut levels = self.lock();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        levels.clear();
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<u8, Arc<Vec<Cluster>>>> {
+        self.levels.lock().expect("cluster cache lock poisoned")
+    }
+}
+
+/// Group points on the grid of a zoom level.
+///
+/// Points must be sorted newest first, so samples list the newest stickers.
+#[must_use]
+pub fn cluster(points: &[Point], zoom: u8) -> Vec<Cluster> {
+    let cells_per_side = 256.0 * 2f64.powi(i32::from(zoom)) / CELL_PIXELS;
+    // Points on the right or bottom edge of the map belong to the last cell
+    #[allow(clippy::cast_possible_truncation)]
+    let last = cells_per_side as i64 - 1;
+
+    // Sum of latitudes, sum of longitudes and members of each cell
+    let mut cells: BTreeMap<(i64, i64), (f64, f64, Vec<i32>)> = BTreeMap::new();
+    for point in points {
+        let (x, y) = mercator(point.latitude, point.longitude);
+        #[allow(clippy::cast_possible_truncation)]
+        let key = (
+            ((x * cells_per_side).floor() as i64).min(last),
+            ((y * cells_per_side).floor() as i64).min(last),
+        );
+
+        let cell = cells.entry(key).or_default();
+        cell.0 += point.latitude;
+        cell.1 += point.longitude;
+        cell.2.push(point.id);
+    }
+
+    cells
+        .into_iter()
+        .map(|((x, y), (latitude, longitude, ids))| {
+            #[allow(clippy::cast_precision_loss)]
+            let count = ids.len() as f64;
+            Cluster {
+                latitude: latitude / count,
+                longitude: longitude / count,
+                count: ids.len(),
+                sample_ids: ids.into_iter().take(SAMPLE_SIZE).collect(),
+                cell: cell_bounds(x, y, cells_per_side),
+            }
+        })
+        .collect()
+}
+
+/// Bounds of a grid cell.
+///
+/// Points beyond the Mercator latitude limit are clamped into the first and
+/// last rows, so those rows extend to the poles.
+#[allow(clippy::cast_precision_loss)]
+fn cell_bounds(x: i64, y: i64, cells_per_side: f64) -> GeoBox {
+    let longitude = |x: i64| {
+        (x as f64 / cells_per_side)
+            .mul_add(360.0, -180.0)
+            .clamp(-180.0, 180.0)
+    };
+    let latitude = |y: i64| {
+        if y <= 0 {
+            90.0
+        } else if y as f64 >= cells_per_side {
+            -90.0
+        } else {
+            (std::f64::consts::PI * (1.0 - 2.0 * y as f64 / cells_per_side))
+                .sinh()
+                .atan()
+                .to_degrees()
+        }
+    };
+
+    GeoBox {
+        min_lat: latitude(y + 1),
+        max_lat: latitude(y),
+        min_lon: longitude(x),
+        max_lon: longitude(x + 1),
+    }
+}
+
+/// Project a point to Web Mercator, both coordinates in `[0, 1]`.
+fn mercator(latitude: f64, longitude: f64) -> (f64, f64) {
+    let latitude = latitude
+        .clamp(-MAX_MERCATOR_LAT, MAX_MERCATOR_LAT)
+        .to_radians();
+    let x = (longitude + 180.0) / 360.0;
+    let y = (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln() / std::f64::consts::PI) / 2.0;
+
+    (x.clamp(0.0, 1.0), y.clamp(0.0, 1.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(id: i32, latitude: f64, longitude: f64) -> Point {
+        Point {
+            id,
+            latitude,
+            longitude,
+        }
+    }
+
+    /// Two points in Paris, about 1.2 km apart
+    fn paris() -> [Point; 2] {
+        [point(2, 48.8566, 2.3522), point(1, 48.8606, 2.3376)]
+    }
+
+    #[test]
+    fn close_points_merge_at_low_zoom() {
+        let clusters = cluster(&paris(), 3);
+
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].count, 2);
+    }
+
+    #[test]
+    fn close_points_split_at_high_zoom() {
+        let clusters = cluster(&paris(), 18);
+
+        assert_eq!(clusters.len(), 2);
+        assert!(clusters.iter().all(|cluster| cluster.count == 1));
+    }
+
+    #[test]
+    fn cluster_reports_centroid_count_and_newest_samples() {
+        // Newest first, as the handler loads them
+        let points: Vec<_> = (0..7)
+            .rev()
+            .map(|id| point(id, 10.0 + f64::from(id) * 0.01, 20.0 - f64::from(id) * 0.01))
+            .collect();
+
+        let clusters = cluster(&points, 5);
+
+        assert_eq!(clusters.len(), 1);
+        let cluster = &clusters[0];
+        assert_eq!(cluster.count, 7);
+        assert_eq!(cluster.sample_ids, [6, 5, 4, 3, 2]);
+        assert!((cluster.latitude - 10.03).abs() < 1e-9);
+        assert!((cluster.longitude - 19.97).abs() < 1e-9);
+    }
+
+    #[test]
+    fn cell_holds_its_points() {
+        for cluster in cluster(&paris(), 12) {
+            let cell = cluster.cell;
+            assert!(cell.min_lat <= cluster.latitude && cluster.latitude <= cell.max_lat);
+            assert!(cell.min_lon <= cluster.longitude && cluster.longitude <= cell.max_lon);
+        }
+    }
+
+    #[test]
+    fn points_on_the_map_edges_fall_in_the_last_cells() {
+        let points = [point(1, 90.0, 180.0), point(2, -90.0, -180.0)];
+
+        let clusters = cluster(&points, 0);
+
+        // Four cells per side at zoom 0, the corners are opposite
+        assert_eq!(clusters.len(), 2);
+        assert!(
+            clusters
+                .iter()
+                .any(|cluster| cluster.cell.max_lat == 90.0 && cluster.cell.max_lon == 180.0)
+        );
+        assert!(
+            clusters
+                .iter()
+                .any(|cluster| cluster.cell.min_lat == -90.0 && cluster.cell.min_lon == -180.0)
+        );
+    }
+
+    #[test]
+    fn bbox_keeps_clusters_whose_cell_overlaps_it() {
+        // At zoom 4 the cell of this point spans longitudes 5.625 to 11.25
+        let clusters = cluster(&[point(1, 45.0, 10.0)], 4);
+        let cell = &clusters[0].cell;
+        let bbox = |min_lon, max_lon| GeoBox {
+            min_lat: 40.0,
+            max_lat: 50.0,
+            min_lon,
+            max_lon,
+        };
+
+        // The centroid is outside, but the edge of its cell is inside
+        assert!(bbox(10.5, 20.0).intersects(cell));
+        assert!(!bbox(12.0, 20.0).intersects(cell));
+    }
+
+    #[test]
+    fn cache_drops_levels_computed_before_an_invalidation() {
+        let cache = ClusterCache::default();
+        let level = Arc::new(cluster(&paris(), 3));
+
+        let stale = cache.generation();
+        cache.insert(3, stale, level.clone());
+        assert!(cache.get(3).is_some());
+
+        cache.invalidate();
+        assert!(cache.get(3).is_none());
+        cache.insert(3, stale, level.clone());
+        assert!(cache.get(3).is_none());
+
+        cache.insert(3, cache.generation(), level);
+        assert_eq!(cache.get(3).unwrap().len(), 1);
+    }
+}
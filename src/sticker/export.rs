@@ -0,0 +1,422 @@
+//! Sticker export formats
+//!
+//! Builders turning sticker rows into documents for map and GPS tools:
+//! - GeoJSON (RFC 7946) `FeatureCollection`, one `Point` feature per sticker
+//! - GPX 1.1, one waypoint per sticker, for GPS apps
+//! - KML 2.2, one `Placemark` per sticker with its pictures linked in the
+//!   description, for Google Earth
+//!
+//! Exported files are opened outside the browser (QGIS, GPS apps, Google
+//! Earth), so their picture links are made absolute with `PUBLIC_BASE_URL`.
+
+use anyhow::Context;
+use axum::http::{HeaderMap, header};
+use chrono::SecondsFormat;
+use serde_json::{Value, json};
+use std::{fmt::Write, sync::Arc};
+
+use crate::{entities::stickers, error::ApiResult};
+
+/// Media type of GeoJSON documents
+pub const GEOJSON_CONTENT_TYPE: &str = "application/geo+json";
+
+/// Media type of GPX documents
+pub const GPX_CONTENT_TYPE: &str = "application/gpx+xml";
+
+/// Media type of KML documents
+pub const KML_CONTENT_TYPE: &str = "application/vnd.google-earth.kml+xml";
+
+/// Public origin of the server, e.g. `https://api.example.com`.
+#[derive(Debug, Clone)]
+pub struct PublicBaseUrl(Arc<str>);
+
+impl PublicBaseUrl {
+    /// Parse an `http` or `https` origin, optionally with a path prefix.
+    ///
+    /// # Errors
+    /// Returns an error if the value is not an absolute `http(s)` URL, or has
+    /// a query or fragment.
+    pub fn from_spec(spec: &str) -> Result<Self, String> {
+        let url = url::Url::parse(spec.trim())
+            .map_err(|e| format!("PUBLIC_BASE_URL is not a valid URL: {e}"))?;
+
+        if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+            return Err("PUBLIC_BASE_URL must be an http or https URL".to_string());
+        }
+
+        if url.query().is_some() || url.fragment().is_some() {
+            return Err("PUBLIC_BASE_URL must not have a query or fragment".to_string());
+        }
+
+        Ok(Self(url.as_str().trim_end_matches('/').into()))
+    }
+
+    /// Turn a server path into an absolute URL, URLs are kept as they are.
+    #[must_use]
+    pub fn absolute(&self, path: &str) -> String {
+        if path.starts_with("http://") || path.starts_with("https://") {
+            return path.to_string();
+        }
+
+        let separator = if path.starts_with('/') { "" } else { "/" };
+        format!("{}{separator}{path}", self.0)
+    }
+}
+
+/// Whether a request asks for GeoJSON through its `Accept` header.
+#[must_use]
+pub fn accepts_geojson(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::ACCEPT)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|accept| accept.contains(GEOJSON_CONTENT_TYPE))
+}
+
+/// Build a GeoJSON `FeatureCollection` of stickers.
+///
+/// Coordinates are `[longitude, latitude]`, as required by RFC 7946, and
+/// pictures become absolute URLs.
+///
+/// # Errors
+/// Returns 500 if the stored pictures of a sticker are not a list of strings.
+pub fn geojson(stickers: Vec<stickers::Model>, base_url: &PublicBaseUrl) -> ApiResult<Value> {
+    let features = stickers
+        .into_iter()
+        .map(|model| {
+            let pictures: Vec<String> = pictures(&model)?
+                .iter()
+                .map(|picture| base_url.absolute(picture))
+                .collect();
+
+            Ok(json!({
+                "type": "Feature",
+                "id": model.id,
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": [model.longitude, model.latitude],
+                },
+                "properties": {
+                    "name": model.name,
+                    "place_name": model.place_name,
+                    "country_code": model.country_code,
+                    "region": model.region,
+                    "pictures": pictures,
+                    "placed_at": model.placed_at.map(|placed_at| placed_at.and_utc().to_rfc3339()),
+                    "created_at": model.created_at.and_utc().to_rfc3339(),
+                    "updated_at": model.updated_at.and_utc().to_rfc3339(),
+                },
+            }))
+        })
+        .collect::<ApiResult<Vec<_>>>()?;
+
+    Ok(json!({
+        "type": "FeatureCollection",
+        "features": features,
+    }))
+}
+
+/// Build a GPX document with one waypoint per sticker.
+///
+/// The waypoint description is the place name, and pictures become absolute links.
+///
+/// # Errors
+/// Returns 500 if the stored pictures of a sticker are not a list of strings.
+pub fn gpx(stickers: &[stickers::Model], base_url: &PublicBaseUrl) -> ApiResult<String> {
+    let mut doc = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<gpx version="1.1" creator="vps_back" xmlns="http://www.topografix.com/GPX/1/1">"#,
+        "\n",
+    ));
+
+    for model in stickers {
+        let _ = writeln!(
+            doc,
+            r#"  <wpt lat="{}" lon="{}">"#,
+            model.latitude, model.longitude
+        );
+        let _ = writeln!(doc, "    <time>{}</time>", timestamp(model));
+        let _ = writeln!(doc, "    <name>{}</name>", escape(&model.name));
+        let _ = writeln!(doc, "    <desc>{}</desc>", escape(&model.place_name));
+        for picture in pictures(model)? {
+            let _ = writeln!(
+                doc,
+                r#"    <link href="{}"/>"#,
+                escape(&base_url.absolute(&picture))
+            );
+        }
+        doc.push_str("  </wpt>\n");
+    }
+
+    doc.push_str("</gpx>\n");
+    Ok(doc)
+}
+
+/// Build a KML document with one `Placemark` per sticker.
+///
+/// The description is HTML with the place name and the pictures as linked
+/// images, with absolute URLs.
+///
+/// # Errors
+/// Returns 500 if the stored pictures of a sticker are not a list of strings.
+pub fn kml(stickers: &[stickers::Model], base_url: &PublicBaseUrl) -> ApiResult<String> {
+    let mut doc = String::from(concat!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>"#,
+        "\n",
+        r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#,
+        "\n",
+        "  <Document>\n",
+        "    <name>Stickers</name>\n",
+    ));
+
+    for model in stickers {
+        let mut description = format!("<p>{}</p>", escape(&model.place_name));
+        for picture in pictures(model)? {
+            let picture = escape(&base_url.absolute(&picture));
+            let _ = write!(
+                description,
+                r#"<p><a href="{picture}"><img src="{picture}" width="300"/></a></p>"#
+            );
+        }
+
+        let _ = writeln!(doc, r#"    <Placemark id="sticker-{}">"#, model.id);
+        let _ = writeln!(doc, "      <name>{}</name>", escape(&model.name));
+        let _ = writeln!(
+            doc,
+            "      <description>{}</description>",
+            escape(&description)
+        );
+        let _ = writeln!(
+            doc,
+            "      <TimeStamp><when>{}</when></TimeStamp>",
+            timestamp(model)
+        );
+        let _ = writeln!(
+            doc,
+            "      <Point><coordinates>{},{}</coordinates></Point>",
+            model.longitude, model.latitude
+        );
+        doc.push_str("    </Placemark>\n");
+    }
+
+    doc.push_str("  </Document>\n</kml>\n");
+    Ok(doc)
+}
+
+/// Parse the stored picture list of a sticker.
+fn pictures(model: &stickers::Model) -> ApiResult<Vec<String>> {
+    Ok(serde_json::from_value(model.pictures.clone()).context("Failed to parse pictures JSON")?)
+}
+
+/// Placement time of a sticker, or its creation time when unknown, as an
+/// XML Schema `dateTime` in UTC.
+fn timestamp(model: &stickers::Model) -> String {
+    model
+        .placed_at
+        .unwrap_or(model.created_at)
+        .and_utc()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Escape text for XML content and attribute values.
+///
+/// Characters XML 1.0 does not allow (control characters other than tab and
+/// line breaks, U+FFFE and U+FFFF) are dropped, as no escape can carry them.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\t' | '\n' | '\r' => escaped.push(c),
+            '\u{0}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}' => {}
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::sea_orm_active_enums::Visibility;
+    use axum::http::HeaderValue;
+    use chrono::NaiveDate;
+
+    fn base_url() -> PublicBaseUrl {
+        PublicBaseUrl::from_spec("https://api.example.com/").unwrap()
+    }
+
+    fn sticker(name: &str) -> stickers::Model {
+        let at = NaiveDate::from_ymd_opt(2026, 10, 1)
+            .unwrap()
+            .and_hms_opt(8, 30, 0)
+            .unwrap();
+        stickers::Model {
+            id: 7,
+            name: name.to_string(),
+            latitude: 48.857,
+            longitude: 2.341,
+            place_name: "Paris".to_string(),
+            pictures: json!(["/static/stickers/7/a.jpg", "https://cdn.example.org/b.jpg"]),
+            visibility: Visibility::Public,
+            placed_at: None,
+            country_code: Some("FR".to_string()),
+            region: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    fn accept(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::ACCEPT, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn geojson_coordinates_are_longitude_first() {
+        let collection = geojson(vec![sticker("Pont Neuf")], &base_url()).unwrap();
+
+        assert_eq!(collection["type"], "FeatureCollection");
+        let feature = &collection["features"][0];
+        assert_eq!(feature["id"], 7);
+        assert_eq!(feature["geometry"]["type"], "Point");
+        assert_eq!(feature["geometry"]["coordinates"], json!([2.341, 48.857]));
+        assert_eq!(feature["properties"]["name"], "Pont Neuf");
+        assert_eq!(feature["properties"]["placed_at"], Value::Null);
+    }
+
+    #[test]
+    fn geojson_pictures_are_absolute() {
+        let collection = geojson(vec![sticker("Pont Neuf")], &base_url()).unwrap();
+
+        assert_eq!(
+            collection["features"][0]["properties"]["pictures"],
+            json!([
+                "https://api.example.com/static/stickers/7/a.jpg",
+                "https://cdn.example.org/b.jpg"
+            ])
+        );
+    }
+
+    #[test]
+    fn base_url_keeps_its_path_prefix() {
+        let base_url = PublicBaseUrl::from_spec("https://example.com/api/").unwrap();
+
+        assert_eq!(
+            base_url.absolute("/static/a.jpg"),
+            "https://example.com/api/static/a.jpg"
+        );
+        assert_eq!(
+            base_url.absolute("static/a.jpg"),
+            "https://example.com/api/static/a.jpg"
+        );
+        assert!(PublicBaseUrl::from_spec("ftp://example.com").is_err());
+        assert!(PublicBaseUrl::from_spec("https://example.com/?page=1").is_err());
+        assert!(PublicBaseUrl::from_spec("/static").is_err());
+    }
+
+    /// Check that a generated document is well-formed: balanced tags, known
+    /// entities and only characters XML 1.0 allows.
+    fn assert_well_formed(doc: &str) {
+        assert!(doc.starts_with(r#"<?xml version="1.0" encoding="UTF-8"?>"#));
+        let forbidden = |c: char| {
+            matches!(
+                c,
+                '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}'
+            )
+        };
+        assert!(
+            !doc.chars().any(forbidden),
+            "forbidden character in {doc:?}"
+        );
+
+        let mut open = Vec::new();
+        let mut rest = doc.split_once("?>").unwrap().1;
+        while let Some(start) = rest.find(['<', '&']) {
+            let (text, markup) = rest.split_at(start);
+            assert!(!text.contains('>'), "unescaped '>' in {text:?}");
+
+            if let Some(entity) = markup.strip_prefix('&') {
+                let end = entity.find(';').expect("unterminated entity");
+                assert!(
+                    ["amp", "lt", "gt", "quot", "apos"].contains(&&entity[..end]),
+                    "unknown entity {}",
+                    &entity[..end]
+                );
+                rest = &entity[end + 1..];
+                continue;
+            }
+
+            let end = markup.find('>').expect("unterminated tag");
+            let tag = &markup[1..end];
+            assert!(!tag.contains('<'), "'<' inside tag {tag:?}");
+            let name = tag
+                .trim_start_matches('/')
+                .split([' ', '/'])
+                .next()
+                .unwrap();
+            if let Some(closed) = tag.strip_prefix('/') {
+                assert_eq!(open.pop().as_deref(), Some(closed), "mismatched tags");
+            } else if !tag.ends_with('/') {
+                open.push(name.to_string());
+            }
+            rest = &markup[end + 1..];
+        }
+
+        assert!(open.is_empty(), "unclosed tags {open:?}");
+    }
+
+    #[test]
+    fn escape_replaces_markup_and_drops_forbidden_characters() {
+        assert_eq!(
+            escape(r#"Tom & "Jerry" <3 'cheese'"#),
+            "Tom &amp; &quot;Jerry&quot; &lt;3 &apos;cheese&apos;"
+        );
+        assert_eq!(escape("bell\u{7}\u{0}null\u{1b}[0m"), "bellnull[0m");
+        assert_eq!(
+            escape("tab\tline\r\nend\u{fffe}\u{ffff}"),
+            "tab\tline\r\nend"
+        );
+        assert_eq!(escape("Île-de-France 🗼"), "Île-de-France 🗼");
+    }
+
+    #[test]
+    fn gpx_is_well_formed() {
+        let doc = gpx(&[sticker("<Pont> & \u{1}Neuf\u{8}")], &base_url()).unwrap();
+
+        assert_well_formed(&doc);
+        assert!(doc.contains(r#"<wpt lat="48.857" lon="2.341">"#));
+        assert!(doc.contains("<name>&lt;Pont&gt; &amp; Neuf</name>"));
+        assert!(doc.contains("<time>2026-10-01T08:30:00Z</time>"));
+        assert!(doc.contains(r#"<link href="https://api.example.com/static/stickers/7/a.jpg"/>"#));
+    }
+
+    #[test]
+    fn kml_is_well_formed() {
+        let doc = kml(&[sticker("Rue \"d'Arcole\" \u{c}")], &base_url()).unwrap();
+
+        assert_well_formed(&doc);
+        assert!(doc.contains("<name>Rue &quot;d&apos;Arcole&quot; </name>"));
+        assert!(doc.contains("<coordinates>2.341,48.857</coordinates>"));
+        // The HTML description is escaped once more as element text
+        assert!(
+            doc.contains("&lt;img src=&quot;https://api.example.com/static/stickers/7/a.jpg&quot;")
+        );
+    }
+
+    #[test]
+    fn geojson_is_chosen_by_accept_header() {
+        assert!(accepts_geojson(&accept("application/geo+json")));
+        assert!(accepts_geojson(&accept(
+            "application/geo+json, application/json;q=0.9"
+        )));
+        assert!(!accepts_geojson(&accept("application/json")));
+        assert!(!accepts_geojson(&accept("*/*")));
+        assert!(!accepts_geojson(&HeaderMap::new()));
+    }
+}
@@ -0,0 +1,217 @@
+//! Multipart bodies of the sticker endpoints
+//!
+//! Fields are read chunk by chunk so an oversized upload is refused as soon as
+//! it crosses its size limit, without buffering the rest of it: the picture
+//! size limit for files, [`MAX_TEXT_BYTES`] for text fields. The whole body is
+//! capped by [`body_limit`].
+
+use anyhow::Context;
+use axum::extract::multipart::{Field, Multipart};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+use super::{
+    models::StickerRequest,
+    photo,
+    pictures::{PictureStore, ProcessedPicture},
+};
+use crate::error::{ApiError, ApiResult, FieldError};
+
+/// Largest text field, in bytes
+pub const MAX_TEXT_BYTES: usize = 4 * 1024;
+
+/// Room left for text fields and part headers on top of the files, in bytes
+const FORM_OVERHEAD_BYTES: usize = 64 * 1024;
+
+/// Largest multipart body holding up to `files` pictures of `max_bytes` each.
+#[must_use]
+pub const fn body_limit(files: usize, max_bytes: usize) -> usize {
+    files
+        .saturating_mul(max_bytes)
+        .saturating_add(FORM_OVERHEAD_BYTES)
+}
+
+/// Read the next field of a multipart body.
+///
+/// # Errors
+/// Returns 400 if the body is malformed.
+pub async fn next_field(multipart: &mut Multipart) -> ApiResult<Option<Field<'_>>> {
+    multipart
+        .next_field()
+        .await
+        .map_err(|e| ApiError::validation(format!("Invalid multipart body: {e}")))
+}
+
+/// Read an uploaded file, up to `max_bytes`.
+///
+/// # Errors
+/// Returns 400 if the body is malformed or the file is too large.
+pub async fn read_file(mut field: Field<'_>, max_bytes: usize) -> ApiResult<Vec<u8>> {
+    let name = field.file_name().unwrap_or_default().to_string();
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|e| ApiError::validation(format!("Invalid multipart body: {e}")))?
+    {
+        if bytes.len() + chunk.len() > max_bytes {
+            return Err(ApiError::validation(format!(
+                "Picture '{name}' exceeds {max_bytes} bytes"
+            )));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+/// Read a text field, up to [`MAX_TEXT_BYTES`].
+///
+/// # Errors
+/// Returns 400 if the body is malformed, the field is too large or not UTF-8.
+pub async fn read_text(mut field: Field<'_>) -> ApiResult<String> {
+    let name = field.name().unwrap_or_default().to_string();
+
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field
+        .chunk()
+        .await
+        .map_err(|e| ApiError::validation(format!("Invalid multipart body: {e}")))?
+    {
+        if bytes.len() + chunk.len() > MAX_TEXT_BYTES {
+            return Err(ApiError::validation(format!(
+                "Field '{name}' exceeds {MAX_TEXT_BYTES} bytes"
+            )));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+
+    String::from_utf8(bytes)
+        .map_err(|_| ApiError::validation(format!("Field '{name}' is not valid UTF-8")))
+}
+
+/// Read a sticker creation form: the fields of [`StickerRequest`] as text
+/// fields (`pictures` may repeat) and a single photo.
+///
+/// Missing `latitude`/`longitude` are read from the photo's GPS tags, and a
+/// missing `placed_at` from its capture time when it is known in UTC.
+///
+/// # Returns
+/// The sticker payload, still to be validated, and the photo stripped of its metadata.
+///
+/// # Errors
+/// Returns 400 if the body is malformed, holds no photo or several, if a
+/// field cannot be parsed, if only one of `latitude` and `longitude` is sent,
+/// or if the position is missing from both the fields and the photo.
+pub async fn sticker_form(
+    mut multipart: Multipart,
+    store: &PictureStore,
+) -> ApiResult<(StickerRequest, ProcessedPicture)> {
+    let mut fields = HashMap::new();
+    let mut pictures = Vec::new();
+    let mut upload = None;
+
+    while let Some(field) = next_field(&mut multipart).await? {
+        let name = field.name().unwrap_or_default().to_string();
+
+        if field.file_name().is_some() {
+            if upload.is_some() {
+                return Err(ApiError::validation(
+                    "Only one picture can be uploaded when creating a sticker",
+                ));
+            }
+            upload = Some(read_file(field, store.max_bytes()).await?);
+        } else {
+            let value = read_text(field).await?;
+            if name == "pictures" {
+                pictures.push(value);
+            } else {
+                fields.insert(name, value);
+            }
+        }
+    }
+
+    let bytes = upload.ok_or_else(|| {
+        ApiError::validation("A picture file is required when creating a sticker from a form")
+    })?;
+
+    let mut errors = Vec::new();
+    let latitude = parse(&mut errors, &fields, "latitude", |v| v.parse::<f64>().ok());
+    let longitude = parse(&mut errors, &fields, "longitude", |v| v.parse::<f64>().ok());
+    let placed_at = parse(&mut errors, &fields, "placed_at", |v| {
+        v.parse::<NaiveDateTime>().ok()
+    });
+    let visibility = parse(&mut errors, &fields, "visibility", |v| {
+        serde_json::from_value(serde_json::Value::String(v.to_string())).ok()
+    });
+
+    // The photo position is only used when the form has neither coordinate
+    let (bytes, placement) = tokio::task::spawn_blocking(move || {
+        let placement = photo::placement(&bytes);
+        (bytes, placement)
+    })
+    .await
+    .context("Photo metadata task failed")?;
+    let sent = ["latitude", "longitude"].map(|name| fields.contains_key(name));
+    let (latitude, longitude) = match (latitude, longitude, &placement.position) {
+        (Some(latitude), Some(longitude), _) => (latitude, longitude),
+        _ if sent.contains(&true) => {
+            for (name, sent) in ["latitude", "longitude"].into_iter().zip(sent) {
+                if !sent {
+                    errors.push(FieldError::new(
+                        name,
+                        "latitude and longitude must be sent together",
+                    ));
+                }
+            }
+            (0.0, 0.0)
+        }
+        (_, _, Ok(position)) => *position,
+        (_, _, Err(message)) => {
+            errors.push(FieldError::new(
+                "picture",
+                format!("{message}, send latitude and longitude"),
+            ));
+            (0.0, 0.0)
+        }
+    };
+
+    if !errors.is_empty() {
+        return Err(ApiError::InvalidFields(errors));
+    }
+
+    let picture = store
+        .process_blocking(bytes)
+        .await?
+        .map_err(|e| ApiError::InvalidFields(vec![FieldError::new("picture", e)]))?;
+
+    let payload = StickerRequest {
+        name: fields.remove("name").unwrap_or_default(),
+        latitude,
+        longitude,
+        place_name: fields.remove("place_name").unwrap_or_default(),
+        country_code: fields.remove("country_code"),
+        region: fields.remove("region"),
+        pictures,
+        visibility: visibility.unwrap_or_default(),
+        placed_at: placed_at.or(placement.taken_at),
+    };
+
+    Ok((payload, picture))
+}
+
+/// Parse an optional text field, recording an error if it is malformed.
+fn parse<T>(
+    errors: &mut Vec<FieldError>,
+    fields: &HashMap<String, String>,
+    name: &str,
+    parser: impl FnOnce(&str) -> Option<T>,
+) -> Option<T> {
+    let value = fields.get(name)?.trim();
+    let parsed = parser(value);
+    if parsed.is_none() {
+        errors.push(FieldError::new(name, format!("cannot parse '{value}'")));
+    }
+    parsed
+}
@@ -0,0 +1,250 @@
+//! Coordinate fuzzing for the public sticker feed
+//!
+//! Public responses never expose exact placements. Depending on
+//! `STICKER_PUBLIC_FUZZING`, coordinates are either rounded to a number of
+//! decimals or moved by a pseudo-random offset within a radius. The offset of
+//! a sticker is derived from its id and the secret `STICKER_FUZZING_SALT`, so
+//! it stays the same across requests and restarts and cannot be averaged away
+//! by collecting many responses.
+
+use sha2::{Digest, Sha256};
+use std::f64::consts::TAU;
+
+/// Meters per degree of latitude
+const METERS_PER_DEGREE: f64 = 111_320.0;
+
+/// Shortest accepted `STICKER_FUZZING_SALT`
+pub const MIN_SALT_LENGTH: usize = 16;
+
+/// How public coordinates are blurred.
+#[derive(Debug, Clone, Copy)]
+pub enum CoordinateFuzzing {
+    /// Exact coordinates
+    None,
+    /// Round to a number of decimals (2 decimals is about 1 km)
+    Round { decimals: u8 },
+    /// Move each sticker by up to `meters` in a direction derived from its id
+    Offset { meters: f64, salt: [u8; 32] },
+}
+
+impl Default for CoordinateFuzzing {
+    fn default() -> Self {
+        Self::Round { decimals: 2 }
+    }
+}
+
+impl CoordinateFuzzing {
+    /// Parse `none`, `round:<decimals>` or `offset:<meters>`.
+    ///
+    /// # Arguments
+    /// * `spec` - The fuzzing specification.
+    /// * `salt` - The secret offsets are derived from, required by `offset:`.
+    ///
+    /// # Errors
+    /// Returns an error describing the expected format, or if `offset:` is
+    /// used without a salt of at least [`MIN_SALT_LENGTH`] characters.
+    pub fn from_spec(spec: &str, salt: Option<&str>) -> Result<Self, String> {
+        let invalid = || {
+            format!(
+                "Invalid coordinate fuzzing `{spec}`, expected `none`, `round:<decimals>` or `offset:<meters>`"
+            )
+        };
+
+        match spec.trim().split_once(':') {
+            None if spec.trim() == "none" => Ok(Self::None),
+            Some(("round", decimals)) => decimals
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|decimals| *decimals <= 8)
+                .map(|decimals| Self::Round { decimals })
+                .ok_or_else(invalid),
+            Some(("offset", meters)) => {
+                let meters = meters
+                    .trim()
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|meters| meters.is_finite() && *meters > 0.0)
+                    .ok_or_else(invalid)?;
+
+                // A salt regenerated on restart would let offsets be averaged away
+                let salt = salt
+                    .filter(|salt| salt.chars().count() >= MIN_SALT_LENGTH)
+                    .ok_or_else(|| {
+                        format!(
+                            "STICKER_FUZZING_SALT must be set to a secret of at least {MIN_SALT_LENGTH} characters when using `offset:` fuzzing"
+                        )
+                    })?;
+
+                Ok(Self::Offset {
+                    meters,
+                    salt: Sha256::digest(salt.as_bytes()).into(),
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Blur the coordinates of a sticker.
+    ///
+    /// # Returns
+    /// The public `(latitude, longitude)`.
+    #[must_use]
+    pub fn apply(&self, id: i32, latitude: f64, longitude: f64) -> (f64, f64) {
+        match *self {
+            Self::None => (latitude, longitude),
+            Self::Round { decimals } => {
+                let factor = 10f64.powi(i32::from(decimals));
+                (
+                    (latitude * factor).round() / factor,
+                    (longitude * factor).round() / factor,
+                )
+            }
+            Self::Offset { meters, salt } => {
+                let digest = Sha256::new()
+                    .chain_update(salt)
+                    .chain_update(id.to_be_bytes())
+                    .finalize();
+                let angle = unit(&digest[..8]) * TAU;
+                // Square root keeps points uniformly spread over the disk
+                let distance = unit(&digest[8..16]).sqrt() * meters;
+
+                let d_lat = distance * angle.cos() / METERS_PER_DEGREE;
+                let d_lon = distance * angle.sin()
+                    / (METERS_PER_DEGREE * latitude.to_radians().cos().max(0.01));
+
+                let latitude = (latitude + d_lat).clamp(-90.0, 90.0);
+                let mut longitude = longitude + d_lon;
+                if longitude > 180.0 {
+                    longitude -= 360.0;
+                } else if longitude < -180.0 {
+                    longitude += 360.0;
+                }
+
+                (latitude, longitude)
+            }
+        }
+    }
+}
+
+/// Map 8 bytes to a number in `[0, 1)`.
+fn unit(bytes: &[u8]) -> f64 {
+    let mut buf = [0u8; 8];
+    buf.copy_from_slice(bytes);
+    #[allow(clippy::cast_precision_loss)]
+    let value = (u64::from_be_bytes(buf) >> 11) as f64;
+    value / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sticker::geo::haversine_km;
+
+    const SALT: &str = "0123456789abcdef-secret";
+
+    fn offset(meters: f64, salt: &str) -> CoordinateFuzzing {
+        CoordinateFuzzing::from_spec(&format!("offset:{meters}"), Some(salt)).unwrap()
+    }
+
+    #[test]
+    fn offset_is_deterministic_per_id_and_salt() {
+        let fuzzing = offset(500.0, SALT);
+
+        assert_eq!(
+            fuzzing.apply(7, 48.8566, 2.3522),
+            offset(500.0, SALT).apply(7, 48.8566, 2.3522)
+        );
+        assert_ne!(
+            fuzzing.apply(7, 48.8566, 2.3522),
+            offset(500.0, "another-secret-salt").apply(7, 48.8566, 2.3522)
+        );
+    }
+
+    #[test]
+    fn offset_differs_across_ids() {
+        let fuzzing = offset(500.0, SALT);
+
+        let positions: Vec<_> = (1..=20)
+            .map(|id| fuzzing.apply(id, 48.8566, 2.3522))
+            .collect();
+
+        for (i, a) in positions.iter().enumerate() {
+            for b in &positions[i + 1..] {
+                assert_ne!(a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn offset_stays_within_the_configured_meters() {
+        let meters = 250.0;
+        let fuzzing = offset(meters, SALT);
+
+        for (latitude, longitude) in [(48.8566, 2.3522), (-33.86, 151.21), (69.65, 18.96)] {
+            let mut farthest: f64 = 0.0;
+            for id in 0..500 {
+                let (lat, lon) = fuzzing.apply(id, latitude, longitude);
+                let km = haversine_km(latitude, longitude, lat, lon);
+                farthest = farthest.max(km);
+            }
+
+            // 1% of slack for the flat-earth approximation of the offset
+            assert!(farthest * 1000.0 <= meters * 1.01, "{farthest} km");
+            assert!(farthest * 1000.0 > meters * 0.5, "{farthest} km");
+        }
+    }
+
+    #[test]
+    fn offset_wraps_longitude_across_the_antimeridian() {
+        let fuzzing = offset(5_000.0, SALT);
+
+        for id in 0..100 {
+            let (lat, lon) = fuzzing.apply(id, 0.0, 179.99);
+            assert!((-180.0..=180.0).contains(&lon), "{lon}");
+            assert!(haversine_km(0.0, 179.99, lat, lon) <= 5.05);
+        }
+    }
+
+    #[test]
+    fn round_keeps_the_configured_decimals() {
+        let fuzzing = CoordinateFuzzing::from_spec("round:2", None).unwrap();
+
+        assert_eq!(fuzzing.apply(1, 48.856_613, 2.352_222), (48.86, 2.35));
+        assert_eq!(
+            CoordinateFuzzing::from_spec("none", None)
+                .unwrap()
+                .apply(1, 48.856_613, 2.352_222),
+            (48.856_613, 2.352_222)
+        );
+    }
+
+    #[test]
+    fn offset_requires_a_long_enough_salt() {
+        for salt in [None, Some(""), Some("too-short")] {
+            let error = CoordinateFuzzing::from_spec("offset:200", salt).unwrap_err();
+            assert!(error.contains("STICKER_FUZZING_SALT"), "{error}");
+        }
+
+        let exact = "a".repeat(MIN_SALT_LENGTH);
+        assert!(CoordinateFuzzing::from_spec("offset:200", Some(&exact)).is_ok());
+    }
+
+    #[test]
+    fn malformed_specs_are_refused() {
+        for spec in [
+            "",
+            "round",
+            "round:9",
+            "round:-1",
+            "offset:0",
+            "offset:abc",
+            "blur:2",
+        ] {
+            assert!(
+                CoordinateFuzzing::from_spec(spec, Some(SALT)).is_err(),
+                "{spec}"
+            );
+        }
+    }
+}
@@ -0,0 +1,338 @@
+//! Geographic helpers for sticker queries
+//!
+//! Distances use the haversine formula on a spherical Earth, which is
+//! accurate to about 0.5%, plenty for finding nearby stickers. Queries first
+//! narrow candidates with a latitude/longitude bounding box, which can use the
+//! `idx_stickers_lat_lon` index, then compute exact distances in memory.
+
+use sea_orm::{ColumnTrait, Condition};
+
+use crate::entities::stickers;
+
+/// Mean Earth radius in kilometers
+pub const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Great-circle distance in kilometers between two points given in degrees.
+#[must_use]
+pub fn haversine_km(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let d_phi = (lat2 - lat1).to_radians();
+    let d_lambda = (lon2 - lon1).to_radians();
+
+    let a = (d_phi / 2.0).sin().powi(2) + phi1.cos() * phi2.cos() * (d_lambda / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * a.sqrt().min(1.0).asin()
+}
+
+/// A latitude/longitude rectangle, in degrees.
+///
+/// When `min_lon > max_lon` the box crosses the antimeridian and covers
+/// `[min_lon, 180]` and `[-180, max_lon]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeoBox {
+    pub min_lat: f64,
+    pub max_lat: f64,
+    pub min_lon: f64,
+    pub max_lon: f64,
+}
+
+impl GeoBox {
+    /// Smallest box containing every point within `radius_km` of a center.
+    #[must_use]
+    pub fn around(lat: f64, lon: f64, radius_km: f64) -> Self {
+        let angular = radius_km / EARTH_RADIUS_KM;
+        let d_lat = angular.to_degrees();
+        let min_lat = lat - d_lat;
+        let max_lat = lat + d_lat;
+
+        // Near a pole the circle covers every longitude
+        if min_lat <= -90.0 || max_lat >= 90.0 || angular >= std::f64::consts::FRAC_PI_2 {
+            return Self {
+                min_lat: min_lat.max(-90.0),
+                max_lat: max_lat.min(90.0),
+                min_lon: -180.0,
+                max_lon: 180.0,
+            };
+        }
+
+        let d_lon = (angular.sin() / lat.to_radians().cos())
+            .min(1.0)
+            .asin()
+            .to_degrees();
+
+        Self {
+            min_lat,
+            max_lat,
+            min_lon: wrap_longitude(lon - d_lon),
+            max_lon: wrap_longitude(lon + d_lon),
+        }
+    }
+
+    /// Parse a `minLon,minLat,maxLon,maxLat` bounding box.
+    ///
+    /// As in RFC 7946, `minLon > maxLon` describes a box crossing the antimeridian.
+    ///
+    /// # Errors
+    /// Returns the reason the box is malformed.
+    pub fn parse_bbox(value: &str) -> Result<Self, String> {
+        let parts = value
+            .split(',')
+            .map(|part| part.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| {
+                format!("must be four numbers minLon,minLat,maxLon,maxLat, got '{value}'")
+            })?;
+
+        let [min_lon, min_lat, max_lon, max_lat] = parts[..] else {
+            return Err(format!(
+                "must be four numbers minLon,minLat,maxLon,maxLat, got '{value}'"
+            ));
+        };
+
+        if ![min_lon, max_lon]
+            .iter()
+            .all(|lon| (-180.0..=180.0).contains(lon))
+        {
+            return Err("longitudes must be between -180 and 180".to_string());
+        }
+
+        if ![min_lat, max_lat]
+            .iter()
+            .all(|lat| (-90.0..=90.0).contains(lat))
+        {
+            return Err("latitudes must be between -90 and 90".to_string());
+        }
+
+        if min_lat > max_lat {
+            return Err("minLat must not be greater than maxLat".to_string());
+        }
+
+        Ok(Self {
+            min_lat,
+            max_lat,
+            min_lon,
+            max_lon,
+        })
+    }
+
+    /// Whether the box crosses the antimeridian.
+    #[must_use]
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.min_lon > self.max_lon
+    }
+
+    /// Whether a point lies inside the box.
+    #[must_use]
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        let in_lon = if self.crosses_antimeridian() {
+            lon >= self.min_lon || lon <= self.max_lon
+        } else {
+            (self.min_lon..=self.max_lon).contains(&lon)
+        };
+
+        in_lon && (self.min_lat..=self.max_lat).contains(&lat)
+    }
+
+    /// Whether two boxes share at least one point.
+    #[must_use]
+    pub fn intersects(&self, other: &Self) -> bool {
+        let in_lat = self.min_lat <= other.max_lat && other.min_lat <= self.max_lat;
+        let in_lon = self.longitude_ranges().iter().any(|(min, max)| {
+            other
+                .longitude_ranges()
+                .iter()
+                .any(|(other_min, other_max)| min <= other_max && other_min <= max)
+        });
+
+        in_lat && in_lon
+    }
+
+    /// Longitude ranges covered by the box, two when it crosses the antimeridian.
+    fn longitude_ranges(&self) -> Vec<(f64, f64)> {
+        if self.crosses_antimeridian() {
+            vec![(self.min_lon, 180.0), (-180.0, self.max_lon)]
+        } else {
+            vec![(self.min_lon, self.max_lon)]
+        }
+    }
+
+    /// Query condition selecting the stickers inside the box.
+    #[must_use]
+    pub fn condition(&self) -> Condition {
+        let lat = stickers::Column::Latitude.between(self.min_lat, self.max_lat);
+
+        let lon = if self.crosses_antimeridian() {
+            Condition::any()
+                .add(stickers::Column::Longitude.gte(self.min_lon))
+                .add(stickers::Column::Longitude.lte(self.max_lon))
+        } else {
+            Condition::all().add(stickers::Column::Longitude.between(self.min_lon, self.max_lon))
+        };
+
+        Condition::all().add(lat).add(lon)
+    }
+}
+
+/// Bring a longitude back into `[-180, 180]`.
+fn wrap_longitude(lon: f64) -> f64 {
+    if lon < -180.0 {
+        lon + 360.0
+    } else if lon > 180.0 {
+        lon - 360.0
+    } else {
+        lon
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::prelude::*;
+    use sea_orm::{DbBackend, EntityTrait, QueryFilter, QueryTrait};
+
+    const PARIS: (f64, f64) = (48.8566, 2.3522);
+    const LONDON: (f64, f64) = (51.5074, -0.1278);
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn haversine_gives_the_paris_london_distance() {
+        let distance = haversine_km(PARIS.0, PARIS.1, LONDON.0, LONDON.1);
+
+        assert_close(distance, 343.6, 0.5);
+        assert_close(
+            haversine_km(LONDON.0, LONDON.1, PARIS.0, PARIS.1),
+            distance,
+            1e-9,
+        );
+    }
+
+    #[test]
+    fn haversine_is_zero_for_the_same_point() {
+        assert_close(haversine_km(PARIS.0, PARIS.1, PARIS.0, PARIS.1), 0.0, 1e-9);
+    }
+
+    #[test]
+    fn haversine_takes_the_short_way_across_the_antimeridian() {
+        // One degree of longitude on the equator
+        assert_close(haversine_km(0.0, 179.5, 0.0, -179.5), 111.2, 0.1);
+    }
+
+    #[test]
+    fn box_around_contains_the_circle() {
+        let radius_km = 400.0;
+        let around = GeoBox::around(PARIS.0, PARIS.1, radius_km);
+
+        assert!(around.contains(LONDON.0, LONDON.1));
+        assert!(!around.crosses_antimeridian());
+        assert_close(
+            around.max_lat - PARIS.0,
+            (radius_km / EARTH_RADIUS_KM).to_degrees(),
+            1e-9,
+        );
+        // Longitudes shrink with latitude, so the box is wider than it is tall
+        assert!(around.max_lon - around.min_lon > around.max_lat - around.min_lat);
+    }
+
+    #[test]
+    fn box_around_covers_every_longitude_near_a_pole() {
+        let around = GeoBox::around(89.5, 20.0, 100.0);
+
+        assert_eq!(around.min_lon, -180.0);
+        assert_eq!(around.max_lon, 180.0);
+        assert_eq!(around.max_lat, 90.0);
+        assert!(around.contains(89.9, -160.0));
+
+        let south = GeoBox::around(-89.9, 0.0, 50.0);
+        assert_eq!(south.min_lat, -90.0);
+        assert_eq!((south.min_lon, south.max_lon), (-180.0, 180.0));
+    }
+
+    #[test]
+    fn box_around_wraps_across_the_antimeridian() {
+        // Taveuni, Fiji, sits right on the 180th meridian
+        let around = GeoBox::around(-16.8, 179.9, 50.0);
+
+        assert!(around.crosses_antimeridian());
+        assert!(around.min_lon > 179.0 && around.max_lon < -179.0);
+        assert!(around.contains(-16.8, -179.8));
+        assert!(around.contains(-16.8, 179.95));
+        assert!(!around.contains(-16.8, 0.0));
+    }
+
+    #[test]
+    fn bbox_parses_a_normal_box() {
+        let bbox = GeoBox::parse_bbox("2.2, 48.8,2.5,48.9").unwrap();
+
+        assert_eq!(
+            bbox,
+            GeoBox {
+                min_lat: 48.8,
+                max_lat: 48.9,
+                min_lon: 2.2,
+                max_lon: 2.5,
+            }
+        );
+        assert!(!bbox.crosses_antimeridian());
+        assert!(bbox.contains(PARIS.0, PARIS.1));
+        assert!(!bbox.contains(LONDON.0, LONDON.1));
+    }
+
+    #[test]
+    fn bbox_crossing_the_antimeridian_wraps_around() {
+        let bbox = GeoBox::parse_bbox("170,-20,-170,10").unwrap();
+
+        assert!(bbox.crosses_antimeridian());
+        assert!(bbox.contains(0.0, 175.0));
+        assert!(bbox.contains(0.0, -175.0));
+        assert!(bbox.contains(-20.0, 180.0));
+        assert!(!bbox.contains(0.0, 0.0));
+        assert!(!bbox.contains(0.0, 160.0));
+        assert!(!bbox.contains(15.0, 175.0));
+
+        let sql = Stickers::find()
+            .filter(bbox.condition())
+            .build(DbBackend::Postgres)
+            .to_string();
+        assert!(sql.contains(r#""longitude" >= 170"#), "{sql}");
+        assert!(
+            sql.contains(r#"OR "stickers"."longitude" <= -170"#),
+            "{sql}"
+        );
+    }
+
+    #[test]
+    fn bbox_intersects_boxes_on_either_side_of_the_antimeridian() {
+        let crossing = GeoBox::parse_bbox("170,-20,-170,10").unwrap();
+
+        let east = GeoBox::parse_bbox("-175,0,-160,5").unwrap();
+        let west = GeoBox::parse_bbox("160,0,175,5").unwrap();
+        let elsewhere = GeoBox::parse_bbox("0,0,10,5").unwrap();
+
+        assert!(crossing.intersects(&east) && east.intersects(&crossing));
+        assert!(crossing.intersects(&west));
+        assert!(!crossing.intersects(&elsewhere));
+    }
+
+    #[test]
+    fn bbox_rejects_malformed_input() {
+        for (value, reason) in [
+            ("1,2,3", "four numbers"),
+            ("1,2,3,4,5", "four numbers"),
+            ("a,2,3,4", "four numbers"),
+            ("", "four numbers"),
+            ("0,-91,10,10", "latitudes"),
+            ("0,0,10,90.5", "latitudes"),
+            ("-181,0,10,10", "longitudes"),
+            ("0,10,10,0", "minLat"),
+        ] {
+            let error = GeoBox::parse_bbox(value).unwrap_err();
+            assert!(error.contains(reason), "{value}: {error}");
+        }
+    }
+}
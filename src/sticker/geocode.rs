@@ -0,0 +1,426 @@
+//! Offline reverse geocoding
+//!
+//! Sticker coordinates are matched against a local copy of a GeoNames cities
+//! dump (`cities500.txt`, `cities15000.txt`, ...), one tab-separated place per
+//! line, loaded in memory at startup. Region names come from the optional
+//! `admin1CodesASCII.txt`; without it the region is the raw admin1 code.
+//!
+//! Places are bucketed in one-degree cells, so a lookup only measures the
+//! places in the cells around the point.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::{collections::HashMap, sync::Arc};
+
+use super::{
+    geo::{GeoBox, haversine_km},
+    models::StickerRequest,
+};
+
+/// Farthest a place can be from the point it describes
+pub const MAX_DISTANCE_KM: f64 = 50.0;
+
+/// Columns of the GeoNames dump
+const NAME_COLUMN: usize = 1;
+const LATITUDE_COLUMN: usize = 4;
+const LONGITUDE_COLUMN: usize = 5;
+const COUNTRY_COLUMN: usize = 8;
+const ADMIN1_COLUMN: usize = 10;
+
+/// A place of the dataset
+#[derive(Debug)]
+struct City {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country_code: String,
+    region: Option<String>,
+}
+
+/// Places bucketed by one-degree cell
+#[derive(Debug, Default)]
+struct Index {
+    cities: Vec<City>,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl Index {
+    /// Bucket the places of a cities dump, blank lines are skipped.
+    fn parse(cities: &str, regions: &HashMap<String, String>) -> Result<Self> {
+        let mut index = Self::default();
+        for (number, line) in cities.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let city = parse_city(line, regions)
+                .with_context(|| format!("Malformed line {}", number + 1))?;
+
+            index
+                .cells
+                .entry(cell(city.latitude, city.longitude))
+                .or_default()
+                .push(index.cities.len());
+            index.cities.push(city);
+        }
+
+        Ok(index)
+    }
+}
+
+/// Nearest place to a point
+#[derive(Debug, Clone, Serialize)]
+pub struct Place {
+    pub name: String,
+    pub country_code: String,
+    pub region: Option<String>,
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Distance from the looked up point
+    pub distance_km: f64,
+}
+
+/// Reverse geocoder, disabled when no dataset is configured.
+#[derive(Debug, Clone, Default)]
+pub struct Geocoder {
+    index: Option<Arc<Index>>,
+}
+
+impl Geocoder {
+    /// Load the places of a GeoNames dump.
+    ///
+    /// # Arguments
+    /// * `cities_file` - GeoNames cities dump, `None` disables geocoding.
+    /// * `regions_file` - GeoNames `admin1CodesASCII.txt`, for region names.
+    ///
+    /// # Errors
+    /// Returns an error if a file cannot be read or has a malformed line.
+    pub fn load(cities_file: Option<&str>, regions_file: Option<&str>) -> Result<Self> {
+        let Some(cities_file) = cities_file else {
+            return Ok(Self::default());
+        };
+
+        let regions = match regions_file {
+            Some(path) => parse_regions(
+                &std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read regions from {path}"))?,
+            ),
+            None => HashMap::new(),
+        };
+
+        let cities = std::fs::read_to_string(cities_file)
+            .with_context(|| format!("Failed to read cities from {cities_file}"))?;
+        let index = Index::parse(&cities, &regions)
+            .with_context(|| format!("Failed to load cities from {cities_file}"))?;
+
+        tracing::info!("Loaded {} places for reverse geocoding", index.cities.len());
+
+        Ok(Self {
+            index: Some(Arc::new(index)),
+        })
+    }
+
+    /// Whether a dataset is loaded.
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.index.is_some()
+    }
+
+    /// Find the place nearest to a point.
+    ///
+    /// # Returns
+    /// `None` if geocoding is disabled, the point is invalid or no place is
+    /// within [`MAX_DISTANCE_KM`].
+    #[must_use]
+    pub fn nearest(&self, latitude: f64, longitude: f64) -> Option<Place> {
+        let index = self.index.as_ref()?;
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return None;
+        }
+        let area = GeoBox::around(latitude, longitude, MAX_DISTANCE_KM);
+
+        let lon_cells = if area.crosses_antimeridian() {
+            vec![
+                (cell_lon(area.min_lon), cell_lon(180.0)),
+                (cell_lon(-180.0), cell_lon(area.max_lon)),
+            ]
+        } else {
+            vec![(cell_lon(area.min_lon), cell_lon(area.max_lon))]
+        };
+
+        let mut nearest: Option<(f64, &City)> = None;
+        for lat_cell in cell_lat(area.min_lat)..=cell_lat(area.max_lat) {
+            for &(first, last) in &lon_cells {
+                for lon_cell in first..=last {
+                    let Some(ids) = index.cells.get(&(lat_cell, lon_cell)) else {
+                        continue;
+                    };
+                    for &id in ids {
+                        let city = &index.cities[id];
+                        let distance =
+                            haversine_km(latitude, longitude, city.latitude, city.longitude);
+                        if distance <= MAX_DISTANCE_KM
+                            && nearest.is_none_or(|(best, _)| distance < best)
+                        {
+                            nearest = Some((distance, city));
+                        }
+                    }
+                }
+            }
+        }
+
+        nearest.map(|(distance_km, city)| Place {
+            name: city.name.clone(),
+            country_code: city.country_code.clone(),
+            region: city.region.clone(),
+            latitude: city.latitude,
+            longitude: city.longitude,
+            distance_km,
+        })
+    }
+}
+
+impl StickerRequest {
+    /// Fill the place name, country code and region the payload omits from
+    /// the place nearest to its coordinates.
+    ///
+    /// Values the dataset leaves empty are not filled in.
+    pub fn fill_place(&mut self, geocoder: &Geocoder) {
+        let complete = !self.place_name.trim().is_empty()
+            && self.country_code.is_some()
+            && self.region.is_some();
+        if complete {
+            return;
+        }
+
+        let Some(place) = geocoder.nearest(self.latitude, self.longitude) else {
+            return;
+        };
+
+        if self.place_name.trim().is_empty() && !place.name.is_empty() {
+            self.place_name = place.name;
+        }
+        if self.country_code.is_none() && !place.country_code.is_empty() {
+            self.country_code = Some(place.country_code);
+        }
+        if self.region.is_none() {
+            self.region = place.region;
+        }
+    }
+}
+
+/// Parse a line of the cities dump.
+fn parse_city(line: &str, regions: &HashMap<String, String>) -> Result<City> {
+    let columns = line.split('\t').collect::<Vec<_>>();
+    let column = |index: usize| {
+        columns
+            .get(index)
+            .map(|value| value.trim())
+            .with_context(|| format!("missing column {}", index + 1))
+    };
+
+    let latitude = column(LATITUDE_COLUMN)?
+        .parse::<f64>()
+        .ok()
+        .filter(|lat| (-90.0..=90.0).contains(lat))
+        .context("invalid latitude")?;
+    let longitude = column(LONGITUDE_COLUMN)?
+        .parse::<f64>()
+        .ok()
+        .filter(|lon| (-180.0..=180.0).contains(lon))
+        .context("invalid longitude")?;
+
+    let country_code = column(COUNTRY_COLUMN)?.to_string();
+    let admin1 = column(ADMIN1_COLUMN)?;
+    let region = (!admin1.is_empty()).then(|| {
+        regions
+            .get(&format!("{country_code}.{admin1}"))
+            .cloned()
+            .unwrap_or_else(|| admin1.to_string())
+    });
+
+    Ok(City {
+        name: column(NAME_COLUMN)?.to_string(),
+        latitude,
+        longitude,
+        country_code,
+        region,
+    })
+}
+
+/// Parse `admin1CodesASCII.txt` lines (`FR.11<TAB>Île-de-France<TAB>...`) into code to name.
+fn parse_regions(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split('\t');
+            let code = columns.next()?.trim();
+            let name = columns.next()?.trim();
+            (!code.is_empty() && !name.is_empty()).then(|| (code.to_string(), name.to_string()))
+        })
+        .collect()
+}
+
+fn cell(latitude: f64, longitude: f64) -> (i32, i32) {
+    (cell_lat(latitude), cell_lon(longitude))
+}
+
+/// One-degree latitude cell, the pole belongs to the last one.
+#[allow(clippy::cast_possible_truncation)]
+fn cell_lat(latitude: f64) -> i32 {
+    (latitude.floor() as i32).min(89)
+}
+
+/// One-degree longitude cell, the antimeridian belongs to the last one.
+#[allow(clippy::cast_possible_truncation)]
+fn cell_lon(longitude: f64) -> i32 {
+    (longitude.floor() as i32).min(179)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::sea_orm_active_enums::Visibility;
+
+    const REGIONS: &str = "FR.11\tÎle-de-France\tIle-de-France\t3012874\n\
+                           FJ.02\tNorthern\tNorthern\t2205218\n\
+                           \n\
+                           incomplete line\n";
+
+    /// A GeoNames line, with only the columns the parser reads filled
+    fn line(name: &str, latitude: f64, longitude: f64, country: &str, admin1: &str) -> String {
+        format!(
+            "1\t{name}\t{name}\t\t{latitude}\t{longitude}\tP\tPPL\t{country}\t\t{admin1}\t\t\t\t1000\t\t0\tEtc/UTC\t2026-01-01"
+        )
+    }
+
+    fn geocoder(lines: &[String]) -> Geocoder {
+        let index = Index::parse(&lines.join("\n"), &parse_regions(REGIONS)).unwrap();
+        Geocoder {
+            index: Some(Arc::new(index)),
+        }
+    }
+
+    fn request(latitude: f64, longitude: f64) -> StickerRequest {
+        StickerRequest {
+            name: "Sticker".to_string(),
+            latitude,
+            longitude,
+            place_name: String::new(),
+            country_code: None,
+            region: None,
+            pictures: Vec::new(),
+            visibility: Visibility::default(),
+            placed_at: None,
+        }
+    }
+
+    #[test]
+    fn regions_are_parsed_by_code() {
+        let regions = parse_regions(REGIONS);
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions["FR.11"], "Île-de-France");
+        assert_eq!(regions["FJ.02"], "Northern");
+    }
+
+    #[test]
+    fn city_line_is_parsed_with_its_region_name() {
+        let regions = parse_regions(REGIONS);
+
+        let paris = parse_city(&line("Paris", 48.853_41, 2.3488, "FR", "11"), &regions).unwrap();
+        assert_eq!(paris.name, "Paris");
+        assert!((paris.latitude - 48.853_41).abs() < 1e-9);
+        assert!((paris.longitude - 2.3488).abs() < 1e-9);
+        assert_eq!(paris.country_code, "FR");
+        assert_eq!(paris.region.as_deref(), Some("Île-de-France"));
+
+        // Unknown codes are kept as is, a missing one gives no region
+        let lyon = parse_city(&line("Lyon", 45.75, 4.85, "FR", "84"), &regions).unwrap();
+        assert_eq!(lyon.region.as_deref(), Some("84"));
+        let sea = parse_city(&line("Atoll", 10.0, 10.0, "", ""), &regions).unwrap();
+        assert_eq!(sea.region, None);
+    }
+
+    #[test]
+    fn malformed_city_lines_are_refused() {
+        let regions = HashMap::new();
+
+        assert!(parse_city(&line("Nowhere", 91.0, 0.0, "FR", "11"), &regions).is_err());
+        assert!(parse_city(&line("Nowhere", 0.0, 181.0, "FR", "11"), &regions).is_err());
+        assert!(parse_city("1\tParis\tParis\t\tnorth\t2.3", &regions).is_err());
+        assert!(parse_city("1\tParis\tParis\t\t48.8\t2.3\tP", &regions).is_err());
+
+        let error = Index::parse(
+            &format!("\n{}\nbroken", line("Paris", 48.8, 2.3, "FR", "11")),
+            &regions,
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "Malformed line 3");
+    }
+
+    #[test]
+    fn nearest_looks_across_cell_edges() {
+        let geocoder = geocoder(&[
+            line("Far", 48.0, 2.0, "FR", "11"),
+            line("Corner", 48.99, 2.99, "FR", "11"),
+        ]);
+
+        // The point is in the cell north-east of the place
+        let place = geocoder.nearest(49.01, 3.01).unwrap();
+        assert_eq!(place.name, "Corner");
+        assert!(place.distance_km < 3.0);
+        assert_eq!(place.region.as_deref(), Some("Île-de-France"));
+    }
+
+    #[test]
+    fn nearest_looks_across_the_antimeridian() {
+        let geocoder = geocoder(&[line("Vanua", -16.5, 179.9, "FJ", "02")]);
+
+        let place = geocoder.nearest(-16.5, -179.9).unwrap();
+        assert_eq!(place.name, "Vanua");
+        assert!(place.distance_km < 25.0, "{}", place.distance_km);
+        assert!(geocoder.nearest(-16.5, 180.0).is_some());
+    }
+
+    #[test]
+    fn nearest_ignores_places_beyond_the_cutoff() {
+        let geocoder = geocoder(&[line("Paris", 48.8534, 2.3488, "FR", "11")]);
+
+        // About 44 km and 56 km north of Paris
+        assert!(geocoder.nearest(49.25, 2.3488).is_some());
+        assert!(geocoder.nearest(49.36, 2.3488).is_none());
+        assert!(geocoder.nearest(95.0, 0.0).is_none());
+        assert!(Geocoder::default().nearest(48.8534, 2.3488).is_none());
+    }
+
+    #[test]
+    fn fill_place_only_fills_missing_fields() {
+        let geocoder = geocoder(&[line("Paris", 48.8534, 2.3488, "FR", "11")]);
+
+        let mut empty = request(48.86, 2.35);
+        empty.fill_place(&geocoder);
+        assert_eq!(empty.place_name, "Paris");
+        assert_eq!(empty.country_code.as_deref(), Some("FR"));
+        assert_eq!(empty.region.as_deref(), Some("Île-de-France"));
+
+        let mut named = request(48.86, 2.35);
+        named.place_name = "Canal Saint-Martin".to_string();
+        named.region = Some("Paris".to_string());
+        named.fill_place(&geocoder);
+        assert_eq!(named.place_name, "Canal Saint-Martin");
+        assert_eq!(named.country_code.as_deref(), Some("FR"));
+        assert_eq!(named.region.as_deref(), Some("Paris"));
+    }
+
+    #[test]
+    fn fill_place_skips_empty_dataset_values() {
+        let geocoder = geocoder(&[line("Open sea", 0.0, 0.0, "", "")]);
+
+        let mut sticker = request(0.01, 0.01);
+        sticker.fill_place(&geocoder);
+
+        assert_eq!(sticker.place_name, "Open sea");
+        assert_eq!(sticker.country_code, None);
+        assert_eq!(sticker.region, None);
+    }
+}
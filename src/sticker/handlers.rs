@@ -2,24 +2,56 @@
 //!
 //! This module contains all HTTP handlers for sticker-related endpoints:
 //! - GET /stickers - Fetch all stickers
+//! - GET /stickers.geojson - Export stickers as GeoJSON
+//! - GET /stickers.gpx - Export stickers as GPX waypoints
+//! - GET /stickers.kml - Export stickers as KML placemarks
+//! - GET /stickers/nearby - Fetch the stickers closest to a point
+//! - GET /stickers/geocode - Preview the place reverse geocoding gives a point
 //! - GET /stickers/:id - Fetch a single sticker by ID
 //! - POST /stickers - Create a new sticker
+//! - PUT /stickers/:id - Replace a sticker
+//! - PATCH /stickers/:id - Partially update a sticker
+//! - DELETE /stickers/:id - Delete a sticker
+//! - POST /stickers/:id/pictures - Upload pictures of a sticker
+//!
+//! and the public, read-only feed of stickers marked `public`, with fuzzed
+//! coordinates:
+//! - GET /stickers - Fetch public stickers
+//! - GET /stickers/:id - Fetch a single public sticker
+//! - GET /stickers/clusters - Fetch public stickers grouped per zoom level
 
 use anyhow::Context;
 use axum::{
-    Json,
-    extract::{Path, Query, State},
+    Extension, Json,
+    extract::{FromRequest, Multipart, Path, Query, Request, State},
+    http::{HeaderMap, header},
+    response::{IntoResponse, Response},
 };
+use chrono::NaiveDateTime;
 use sea_orm::{
-    ActiveModelTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryOrder, QuerySelect, Set,
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
 };
 use serde_json::json;
+use std::sync::Arc;
 use tracing::info;
 
-use super::models::{StickerRequest, StickerResponse};
+use super::{
+    cluster::{self, ClusterCache, Point},
+    export::{self, GEOJSON_CONTENT_TYPE, GPX_CONTENT_TYPE, KML_CONTENT_TYPE, PublicBaseUrl},
+    form,
+    fuzz::CoordinateFuzzing,
+    geo::{GeoBox, haversine_km},
+    geocode::Geocoder,
+    models::{
+        ClusterParams, GeocodeParams, NearbyParams, PictureView, StickerFilter, StickerPatch,
+        StickerPictures, StickerRequest, StickerResponse, StickerViewParams,
+    },
+    pictures::PictureStore,
+};
 use crate::{
     data_response, data_response_with_metadata,
-    entities::{prelude::*, stickers},
+    entities::{prelude::*, sea_orm_active_enums::Visibility, stickers},
     error::{ApiError, ApiResult},
     pagination::PaginationParams,
     response::Metadata,
@@ -27,29 +59,51 @@ use crate::{
 
 /// Handles GET requests to fetch all stickers.
 ///
+/// With `bbox=minLon,minLat,maxLon,maxLat`, only the stickers inside the box
+/// are returned; `minLon > maxLon` selects a box crossing the antimeridian.
+///
+/// Requests accepting `application/geo+json` get the GeoJSON export instead,
+/// see [`get_stickers_geojson`].
+///
 /// # Arguments
 /// * `State(db)` - The database connection.
+/// * `Extension(store)` - Lists the picture variants written to disk.
+/// * `Extension(base_url)` - The public origin used for GeoJSON picture links.
+/// * `headers` - The request headers, checked for a GeoJSON `Accept`.
 /// * `Query(params)` - Pagination parameters (page, limit).
+/// * `Query(filter)` - Optional filters (bbox).
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
 ///
 /// # Returns
-/// * `ApiResult<Json<Value>>` - JSON response containing all stickers ordered by creation date (newest first) with pagination metadata.
+/// * `ApiResult<Response>` - JSON response containing all stickers ordered by creation date (newest first) with pagination metadata.
 ///
 /// # Errors
-/// Returns an error if the database query fails.
+/// Returns 400 if a filter is invalid, or an error if the database query fails.
 pub async fn get_all_stickers(
     State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
+    Extension(base_url): Extension<PublicBaseUrl>,
+    headers: HeaderMap,
     Query(mut params): Query<PaginationParams>,
-) -> ApiResult<Json<serde_json::Value>> {
+    Query(filter): Query<StickerFilter>,
+    Query(view): Query<StickerViewParams>,
+) -> ApiResult<Response> {
+    if export::accepts_geojson(&headers) {
+        return get_stickers_geojson(State(db), Extension(base_url), Query(filter)).await;
+    }
+
     info!(
-        "GET `/stickers` endpoint called with page={}, limit={}",
-        params.page, params.limit
+        "GET `/stickers` endpoint called with page={}, limit={}, bbox={:?}",
+        params.page, params.limit, filter.bbox
     );
 
     // Validate pagination parameters
     params.validate();
 
     // Create base query
-    let query = Stickers::find().order_by_desc(stickers::Column::CreatedAt);
+    let query = Stickers::find()
+        .filter(filter.condition()?)
+        .order_by_desc(stickers::Column::CreatedAt);
 
     // Count total items
     #[allow(clippy::cast_possible_truncation)]
@@ -67,33 +121,14 @@ pub async fn get_all_stickers(
         .await
         .context("Failed to fetch stickers from database")?;
 
-    let stickers: Result<Vec<StickerResponse>, anyhow::Error> = stickers_list
-        .into_iter()
-        .map(|model| {
-            let pictures: Vec<String> =
-                serde_json::from_value(model.pictures).context("Failed to parse pictures JSON")?;
-
-            Ok(StickerResponse {
-                id: i64::from(model.id),
-                name: model.name,
-                latitude: model.latitude,
-                longitude: model.longitude,
-                place_name: model.place_name,
-                pictures,
-                created_at: model.created_at.to_string(),
-                updated_at: model.updated_at.to_string(),
-            })
-        })
-        .collect();
-
-    let stickers = stickers?;
+    let stickers = sticker_responses(&store, stickers_list, view.pictures).await?;
 
-    // Build metadata
+    // Build metadata, keeping the filters in the page links
     let metadata = Metadata::paginated(
         params.page,
         params.limit,
         total_count,
-        "/secure/stickers".to_string(),
+        page_link("/secure/stickers", filter.bbox.as_deref(), view.pictures),
     );
 
     Ok(data_response_with_metadata(
@@ -101,14 +136,145 @@ pub async fn get_all_stickers(
             "stickers": stickers
         }),
         &metadata,
-    ))
+    )
+    .into_response())
+}
+
+/// Handles GET requests to export stickers as a GeoJSON `FeatureCollection`.
+///
+/// Accepts the same filters as the list endpoint, without pagination.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(base_url)` - The public origin used for picture links.
+/// * `Query(filter)` - Optional filters (bbox).
+///
+/// # Returns
+/// * `ApiResult<Response>` - `application/geo+json` document, newest stickers first.
+///
+/// # Errors
+/// Returns 400 if a filter is invalid, or an error if the database query fails.
+pub async fn get_stickers_geojson(
+    State(db): State<DatabaseConnection>,
+    Extension(base_url): Extension<PublicBaseUrl>,
+    Query(filter): Query<StickerFilter>,
+) -> ApiResult<Response> {
+    info!(
+        "GET `/stickers.geojson` endpoint called with bbox={:?}",
+        filter.bbox
+    );
+
+    let stickers = find_filtered_stickers(&db, &filter).await?;
+    let collection = export::geojson(stickers, &base_url)?;
+
+    Ok((
+        [(header::CONTENT_TYPE, GEOJSON_CONTENT_TYPE)],
+        Json(collection),
+    )
+        .into_response())
+}
+
+/// Handles GET requests to fetch the stickers within a radius of a point.
+///
+/// Candidates are selected with a bounding box on `latitude`/`longitude`, then
+/// filtered and ordered by great-circle distance.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(store)` - Lists the picture variants written to disk.
+/// * `Query(params)` - Search center (`lat`, `lon`), `radius_km` (default 10) and `limit` (default 20).
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the stickers, closest first,
+///   each with its `distance_km`.
+///
+/// # Errors
+/// Returns 400 listing every invalid parameter, 500 if the database query fails.
+pub async fn get_nearby_stickers(
+    State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
+    Query(params): Query<NearbyParams>,
+    Query(view): Query<StickerViewParams>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!(
+        "GET `/stickers/nearby` endpoint called with lat={}, lon={}, radius_km={}",
+        params.lat, params.lon, params.radius_km
+    );
+
+    params.validate()?;
+
+    let candidates = Stickers::find()
+        .filter(GeoBox::around(params.lat, params.lon, params.radius_km).condition())
+        .all(&db)
+        .await
+        .context("Failed to fetch nearby stickers from database")?;
+
+    let mut nearby = candidates
+        .into_iter()
+        .filter_map(|model| {
+            let distance = haversine_km(params.lat, params.lon, model.latitude, model.longitude);
+            (distance <= params.radius_km).then_some((distance, model))
+        })
+        .collect::<Vec<_>>();
+
+    nearby.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+
+    #[allow(clippy::cast_possible_truncation)]
+    nearby.truncate(params.limit as usize);
+
+    let (distances, models): (Vec<_>, Vec<_>) = nearby.into_iter().unzip();
+    let stickers = sticker_responses(&store, models, view.pictures)
+        .await?
+        .into_iter()
+        .zip(distances)
+        .map(|(sticker, distance)| StickerResponse {
+            distance_km: Some(distance),
+            ..sticker
+        })
+        .collect::<Vec<_>>();
+
+    Ok(data_response(json!({
+        "stickers": stickers
+    })))
+}
+
+/// Handles GET requests to preview the place reverse geocoding gives a point.
+///
+/// # Arguments
+/// * `Extension(geocoder)` - The reverse geocoder.
+/// * `Query(params)` - The point (`lat`, `lon`).
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the nearest place,
+///   `null` if none is close enough or geocoding is not configured.
+///
+/// # Errors
+/// Returns 400 listing every invalid parameter.
+pub async fn geocode_preview(
+    Extension(geocoder): Extension<Geocoder>,
+    Query(params): Query<GeocodeParams>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!(
+        "GET `/stickers/geocode` endpoint called with lat={}, lon={}",
+        params.lat, params.lon
+    );
+
+    params.validate()?;
+
+    Ok(data_response(json!({
+        "enabled": geocoder.is_enabled(),
+        "place": geocoder.nearest(params.lat, params.lon),
+    })))
 }
 
 /// Handles GET requests to fetch a single sticker by ID.
 ///
 /// # Arguments
 /// * `State(db)` - The database connection.
+/// * `Extension(store)` - Lists the picture variants written to disk.
 /// * `Path(id)` - The ID of the sticker to fetch.
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
 ///
 /// # Returns
 /// * `ApiResult<Json<Value>>` - JSON response containing the sticker.
@@ -117,52 +283,89 @@ pub async fn get_all_stickers(
 /// Returns an error if the database query fails or the sticker is not found.
 pub async fn get_sticker(
     State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
     Path(id): Path<i32>,
+    Query(view): Query<StickerViewParams>,
 ) -> ApiResult<Json<serde_json::Value>> {
     info!("GET `/stickers/{}` endpoint called", id);
 
-    let model = Stickers::find_by_id(id)
-        .one(&db)
-        .await
-        .with_context(|| format!("Failed to fetch sticker with id {id}"))?
-        .ok_or_else(|| ApiError::not_found(format!("Sticker with id {id} not found")))?;
-
-    let pictures: Vec<String> =
-        serde_json::from_value(model.pictures).context("Failed to parse pictures JSON")?;
-
-    let sticker = StickerResponse {
-        id: i64::from(model.id),
-        name: model.name,
-        latitude: model.latitude,
-        longitude: model.longitude,
-        place_name: model.place_name,
-        pictures,
-        created_at: model.created_at.to_string(),
-        updated_at: model.updated_at.to_string(),
-    };
+    let model = find_sticker(&db, id).await?;
+    let sticker = sticker_response(&store, model, view.pictures).await?;
 
     Ok(data_response(json!({
         "sticker": sticker
     })))
 }
 
+/// Largest JSON body accepted by sticker creation, the axum default
+const JSON_BODY_LIMIT: usize = 2 * 1024 * 1024;
+
 /// Handles POST requests to create a new sticker.
 ///
+/// Takes either a JSON [`StickerRequest`] or a `multipart/form-data` body with
+/// the same fields and a photo, see [`form::sticker_form`]. A form without
+/// `latitude` and `longitude` takes them from the photo's GPS tags, and the
+/// photo becomes the sticker's first uploaded picture.
+///
+/// An omitted place name, country code or region is filled from the nearest
+/// known place, see [`Geocoder`].
+///
 /// # Arguments
 /// * `State(db)` - The database connection.
-/// * `Json(payload)` - The request payload containing sticker data.
+/// * `Extension(store)` - Where the photo is stored, and the size limit.
+/// * `Extension(geocoder)` - Fills the omitted place name, country code and region.
+/// * `Extension(clusters)` - The cluster cache, invalidated by the change.
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
+/// * `request` - The request, with a JSON or multipart body.
 ///
 /// # Returns
-/// * `ApiResult<Json<Value>>` - JSON response containing the created sticker.
+/// * `ApiResult<Response>` - JSON response containing the created sticker.
 ///
 /// # Errors
-/// Returns an error if the database operation fails or JSON serialization fails.
+/// Returns 400 listing every invalid field, including a photo without GPS
+/// position when the form has none, or an error if the database operation,
+/// JSON serialization or writing the photo fails.
 pub async fn create_sticker(
     State(db): State<DatabaseConnection>,
-    Json(payload): Json<StickerRequest>,
-) -> ApiResult<Json<serde_json::Value>> {
+    Extension(store): Extension<PictureStore>,
+    Extension(geocoder): Extension<Geocoder>,
+    Extension(clusters): Extension<ClusterCache>,
+    Query(view): Query<StickerViewParams>,
+    request: Request,
+) -> ApiResult<Response> {
+    let is_form = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|content_type| content_type.starts_with("multipart/form-data"));
+
+    let (mut payload, photo) = if is_form {
+        let multipart = Multipart::from_request(request, &())
+            .await
+            .map_err(|e| ApiError::validation(e.body_text()))?;
+        let (payload, photo) = form::sticker_form(multipart, &store).await?;
+        (payload, Some(photo))
+    } else {
+        // The route lifts the body limit for photos, JSON keeps the default one
+        let (parts, body) = request.into_parts();
+        let bytes = axum::body::to_bytes(body, JSON_BODY_LIMIT)
+            .await
+            .map_err(|_| {
+                ApiError::validation(format!("Request body exceeds {JSON_BODY_LIMIT} bytes"))
+            })?;
+        match Json::<StickerRequest>::from_request(Request::from_parts(parts, bytes.into()), &())
+            .await
+        {
+            Ok(Json(payload)) => (payload, None),
+            Err(rejection) => return Ok(rejection.into_response()),
+        }
+    };
+
     info!("POST `/stickers` endpoint called for: {}", payload.name);
 
+    payload.fill_place(&geocoder);
+    payload.validate()?;
+
     let pictures_json =
         serde_json::to_value(&payload.pictures).context("Failed to serialize pictures to JSON")?;
 
@@ -171,30 +374,911 @@ pub async fn create_sticker(
         latitude: Set(payload.latitude),
         longitude: Set(payload.longitude),
         place_name: Set(payload.place_name),
+        country_code: Set(payload.country_code),
+        region: Set(payload.region),
         pictures: Set(pictures_json),
+        visibility: Set(payload.visibility),
+        placed_at: Set(payload.placed_at),
         ..Default::default()
     };
 
-    let model = new_sticker
-        .insert(&db)
+    // The photo is stored under the sticker id, the sticker is only kept if it is written
+    let txn = db.begin().await.context("Failed to start transaction")?;
+
+    let mut model = new_sticker
+        .insert(&txn)
         .await
         .context("Failed to insert new sticker into database")?;
 
-    let pictures: Vec<String> = serde_json::from_value(model.pictures)
-        .context("Failed to parse pictures JSON from created sticker")?;
-
-    let sticker = StickerResponse {
-        id: i64::from(model.id),
-        name: model.name,
-        latitude: model.latitude,
-        longitude: model.longitude,
-        place_name: model.place_name,
-        pictures,
-        created_at: model.created_at.to_string(),
-        updated_at: model.updated_at.to_string(),
-    };
+    if let Some(photo) = photo {
+        let id = model.id;
+        let saved = async {
+            let path = store.save(id, &photo).await?;
+
+            let mut pictures = payload.pictures;
+            if !pictures.contains(&path) {
+                pictures.insert(0, path);
+            }
+
+            let mut sticker: stickers::ActiveModel = model.into();
+            sticker.pictures =
+                Set(serde_json::to_value(&pictures)
+                    .context("Failed to serialize pictures to JSON")?);
+            let model = sticker
+                .update(&txn)
+                .await
+                .context("Failed to record the sticker photo")?;
+
+            txn.commit().await.context("Failed to commit new sticker")?;
+            anyhow::Ok(model)
+        }
+        .await;
+
+        // The sticker was rolled back, so is its picture directory
+        model = match saved {
+            Ok(model) => model,
+            Err(e) => {
+                store.remove_all(id).await;
+                return Err(e.into());
+            }
+        };
+    } else {
+        txn.commit().await.context("Failed to commit new sticker")?;
+    }
+
+    clusters.invalidate();
+
+    let sticker = sticker_response(&store, model, view.pictures).await?;
 
     Ok(data_response(json!({
         "sticker": sticker
+    }))
+    .into_response())
+}
+
+/// Handles PUT requests to replace a sticker.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(store)` - Deletes the files of dropped pictures.
+/// * `Extension(geocoder)` - Fills the omitted place name, country code and region.
+/// * `Extension(clusters)` - The cluster cache, invalidated by the change.
+/// * `Path(id)` - The ID of the sticker to replace.
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
+/// * `Json(payload)` - The new sticker data.
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the updated sticker.
+///
+/// # Errors
+/// Returns 400 listing every invalid field, 404 if the sticker is not found,
+/// 500 if the database operation fails.
+pub async fn replace_sticker(
+    State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
+    Extension(geocoder): Extension<Geocoder>,
+    Extension(clusters): Extension<ClusterCache>,
+    Path(id): Path<i32>,
+    Query(view): Query<StickerViewParams>,
+    Json(mut payload): Json<StickerRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!("PUT `/stickers/{}` endpoint called", id);
+
+    payload.fill_place(&geocoder);
+    payload.validate()?;
+
+    let model = find_sticker(&db, id).await?;
+    let removed = removed_pictures(&model, &payload.pictures)?;
+
+    let pictures_json =
+        serde_json::to_value(&payload.pictures).context("Failed to serialize pictures to JSON")?;
+
+    let mut sticker: stickers::ActiveModel = model.into();
+    sticker.name = Set(payload.name);
+    sticker.latitude = Set(payload.latitude);
+    sticker.longitude = Set(payload.longitude);
+    sticker.place_name = Set(payload.place_name);
+    sticker.country_code = Set(payload.country_code);
+    sticker.region = Set(payload.region);
+    sticker.pictures = Set(pictures_json);
+    sticker.visibility = Set(payload.visibility);
+    sticker.placed_at = Set(payload.placed_at);
+    sticker.updated_at = Set(chrono::Utc::now().naive_utc());
+
+    let model = sticker
+        .update(&db)
+        .await
+        .with_context(|| format!("Failed to update sticker with id {id}"))?;
+    clusters.invalidate();
+    store.remove(id, &removed).await;
+
+    Ok(data_response(json!({
+        "sticker": sticker_response(&store, model, view.pictures).await?
+    })))
+}
+
+/// Handles PATCH requests to partially update a sticker.
+///
+/// Only the fields present in the payload change. `pictures` replaces the
+/// picture list, then `remove_pictures` and `add_pictures` are applied.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(store)` - Deletes the files of dropped pictures.
+/// * `Extension(clusters)` - The cluster cache, invalidated by the change.
+/// * `Path(id)` - The ID of the sticker to update.
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
+/// * `Json(payload)` - The fields to change.
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the updated sticker.
+///
+/// # Errors
+/// Returns 400 listing every invalid field, 404 if the sticker is not found,
+/// 500 if the database operation fails.
+pub async fn update_sticker(
+    State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
+    Extension(clusters): Extension<ClusterCache>,
+    Path(id): Path<i32>,
+    Query(view): Query<StickerViewParams>,
+    Json(payload): Json<StickerPatch>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!("PATCH `/stickers/{}` endpoint called", id);
+
+    payload.validate()?;
+
+    let model = find_sticker(&db, id).await?;
+    let (sticker, removed) = apply_patch(model, payload, chrono::Utc::now().naive_utc())?;
+
+    let model = sticker
+        .update(&db)
+        .await
+        .with_context(|| format!("Failed to update sticker with id {id}"))?;
+    clusters.invalidate();
+    store.remove(id, &removed).await;
+
+    Ok(data_response(json!({
+        "sticker": sticker_response(&store, model, view.pictures).await?
+    })))
+}
+
+/// Applies a partial update to a sticker.
+///
+/// # Returns
+/// The changes to write, stamped with `now`, and the pictures the sticker no
+/// longer has.
+///
+/// # Errors
+/// Returns 500 if the stored pictures cannot be parsed.
+fn apply_patch(
+    model: stickers::Model,
+    patch: StickerPatch,
+    now: NaiveDateTime,
+) -> ApiResult<(stickers::ActiveModel, Vec<String>)> {
+    let mut pictures = match patch.pictures {
+        Some(pictures) => pictures,
+        None => serde_json::from_value(model.pictures.clone())
+            .context("Failed to parse pictures JSON")?,
+    };
+    pictures.retain(|picture| !patch.remove_pictures.contains(picture));
+    for picture in patch.add_pictures {
+        if !pictures.contains(&picture) {
+            pictures.push(picture);
+        }
+    }
+
+    let removed = removed_pictures(&model, &pictures)?;
+    let pictures_json =
+        serde_json::to_value(&pictures).context("Failed to serialize pictures to JSON")?;
+
+    let mut sticker: stickers::ActiveModel = model.into();
+    if let Some(name) = patch.name {
+        sticker.name = Set(name);
+    }
+    if let Some(latitude) = patch.latitude {
+        sticker.latitude = Set(latitude);
+    }
+    if let Some(longitude) = patch.longitude {
+        sticker.longitude = Set(longitude);
+    }
+    if let Some(place_name) = patch.place_name {
+        sticker.place_name = Set(place_name);
+    }
+    if let Some(country_code) = patch.country_code {
+        sticker.country_code = Set(country_code);
+    }
+    if let Some(region) = patch.region {
+        sticker.region = Set(region);
+    }
+    if let Some(visibility) = patch.visibility {
+        sticker.visibility = Set(visibility);
+    }
+    if let Some(placed_at) = patch.placed_at {
+        sticker.placed_at = Set(placed_at);
+    }
+    sticker.pictures = Set(pictures_json);
+    sticker.updated_at = Set(now);
+
+    Ok((sticker, removed))
+}
+
+/// Handles DELETE requests to delete a sticker.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(store)` - Deletes the sticker's picture files.
+/// * `Extension(clusters)` - The cluster cache, invalidated by the change.
+/// * `Path(id)` - The ID of the sticker to delete.
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the deleted sticker.
+///
+/// # Errors
+/// Returns 404 if the sticker is not found, 500 if the database operation fails.
+pub async fn delete_sticker(
+    State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
+    Extension(clusters): Extension<ClusterCache>,
+    Path(id): Path<i32>,
+    Query(view): Query<StickerViewParams>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!("DELETE `/stickers/{}` endpoint called", id);
+
+    let model = find_sticker(&db, id).await?;
+
+    Stickers::delete_by_id(id)
+        .exec(&db)
+        .await
+        .with_context(|| format!("Failed to delete sticker with id {id}"))?;
+    clusters.invalidate();
+    store.remove_all(id).await;
+
+    Ok(data_response(json!({
+        "sticker": sticker_response(&store, model, view.pictures).await?
     })))
 }
+
+/// Maximum number of pictures accepted in a single upload request
+pub const MAX_PICTURES_PER_UPLOAD: usize = 10;
+
+/// Handles POST requests to upload pictures of a sticker.
+///
+/// Every file field of the multipart body is checked, stripped of its
+/// metadata and stored under `STATIC_DIR/stickers/<id>/`; the resulting
+/// `/static/...` paths are appended to the sticker's pictures, skipping
+/// pictures it already has.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(store)` - Where pictures are stored, and the size limit.
+/// * `Path(id)` - The ID of the sticker.
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
+/// * `multipart` - The `multipart/form-data` body.
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the updated sticker and the uploaded paths.
+///
+/// # Errors
+/// Returns 400 if the body holds no file, too many files, or a file that is
+/// too large or not a JPEG, PNG or WebP image, 404 if the sticker is not
+/// found, 500 if a file cannot be written or the database operation fails.
+pub async fn upload_sticker_pictures(
+    State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
+    Path(id): Path<i32>,
+    Query(view): Query<StickerViewParams>,
+    mut multipart: Multipart,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!("POST `/stickers/{}/pictures` endpoint called", id);
+
+    find_sticker(&db, id).await?;
+
+    // Every file is checked before any is written, so a refused file leaves nothing behind
+    let mut processed = Vec::new();
+    while let Some(field) = form::next_field(&mut multipart).await? {
+        // Plain form fields carry no file
+        if field.file_name().is_none() {
+            continue;
+        }
+        if processed.len() == MAX_PICTURES_PER_UPLOAD {
+            return Err(ApiError::validation(format!(
+                "At most {MAX_PICTURES_PER_UPLOAD} pictures can be uploaded at once"
+            )));
+        }
+
+        let name = field.file_name().unwrap_or_default().to_string();
+        let bytes = form::read_file(field, store.max_bytes()).await?;
+
+        processed.push(
+            store
+                .process_blocking(bytes)
+                .await?
+                .map_err(|e| ApiError::validation(format!("Picture '{name}': {e}")))?,
+        );
+    }
+
+    if processed.is_empty() {
+        return Err(ApiError::validation("No picture uploaded"));
+    }
+
+    // Lock the row so concurrent uploads don't overwrite each other's pictures
+    let txn = db.begin().await.context("Failed to start transaction")?;
+
+    let model = Stickers::find_by_id(id)
+        .lock_exclusive()
+        .one(&txn)
+        .await
+        .with_context(|| format!("Failed to fetch sticker with id {id}"))?
+        .ok_or_else(|| ApiError::not_found(format!("Sticker with id {id} not found")))?;
+
+    let mut pictures: Vec<String> =
+        serde_json::from_value(model.pictures.clone()).context("Failed to parse pictures JSON")?;
+
+    let uploaded = processed
+        .iter()
+        .map(|picture| PictureStore::url(id, picture))
+        .fold(Vec::new(), |mut uploaded, path| {
+            if !uploaded.contains(&path) {
+                uploaded.push(path);
+            }
+            uploaded
+        });
+    // Files of pictures the sticker already has are not removed on failure
+    let added = uploaded
+        .iter()
+        .filter(|path| !pictures.contains(path))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let saved = async {
+        for picture in &processed {
+            store.save(id, picture).await?;
+        }
+
+        pictures.extend(added.iter().cloned());
+
+        let mut sticker: stickers::ActiveModel = model.into();
+        sticker.pictures =
+            Set(serde_json::to_value(&pictures).context("Failed to serialize pictures to JSON")?);
+        sticker.updated_at = Set(chrono::Utc::now().naive_utc());
+
+        let model = sticker
+            .update(&txn)
+            .await
+            .with_context(|| format!("Failed to update sticker with id {id}"))?;
+
+        txn.commit()
+            .await
+            .context("Failed to commit sticker pictures")?;
+        anyhow::Ok(model)
+    }
+    .await;
+
+    let model = match saved {
+        Ok(model) => model,
+        Err(e) => {
+            store.remove(id, &added).await;
+            return Err(e.into());
+        }
+    };
+
+    Ok(data_response(json!({
+        "sticker": sticker_response(&store, model, view.pictures).await?,
+        "uploaded": uploaded,
+    })))
+}
+
+/// Handles GET requests to export stickers as GPX waypoints.
+///
+/// Accepts the same filters as the list endpoint, without pagination.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(base_url)` - The public origin used for picture links.
+/// * `Query(filter)` - Optional filters (bbox).
+///
+/// # Returns
+/// * `ApiResult<Response>` - `application/gpx+xml` document, newest stickers first.
+///
+/// # Errors
+/// Returns 400 if a filter is invalid, or an error if the database query fails.
+pub async fn get_stickers_gpx(
+    State(db): State<DatabaseConnection>,
+    Extension(base_url): Extension<PublicBaseUrl>,
+    Query(filter): Query<StickerFilter>,
+) -> ApiResult<Response> {
+    info!(
+        "GET `/stickers.gpx` endpoint called with bbox={:?}",
+        filter.bbox
+    );
+
+    let stickers = find_filtered_stickers(&db, &filter).await?;
+    let document = export::gpx(&stickers, &base_url)?;
+
+    Ok(([(header::CONTENT_TYPE, GPX_CONTENT_TYPE)], document).into_response())
+}
+
+/// Handles GET requests to export stickers as KML placemarks.
+///
+/// Accepts the same filters as the list endpoint, without pagination.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(base_url)` - The public origin used for picture links.
+/// * `Query(filter)` - Optional filters (bbox).
+///
+/// # Returns
+/// * `ApiResult<Response>` - `application/vnd.google-earth.kml+xml` document, newest stickers first.
+///
+/// # Errors
+/// Returns 400 if a filter is invalid, or an error if the database query fails.
+pub async fn get_stickers_kml(
+    State(db): State<DatabaseConnection>,
+    Extension(base_url): Extension<PublicBaseUrl>,
+    Query(filter): Query<StickerFilter>,
+) -> ApiResult<Response> {
+    info!(
+        "GET `/stickers.kml` endpoint called with bbox={:?}",
+        filter.bbox
+    );
+
+    let stickers = find_filtered_stickers(&db, &filter).await?;
+    let document = export::kml(&stickers, &base_url)?;
+
+    Ok(([(header::CONTENT_TYPE, KML_CONTENT_TYPE)], document).into_response())
+}
+
+/// Handles GET requests to the public sticker feed.
+///
+/// Only stickers marked `public` are listed, with their coordinates fuzzed
+/// according to `STICKER_PUBLIC_FUZZING`.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(store)` - Lists the picture variants written to disk.
+/// * `Extension(fuzzing)` - The configured coordinate fuzzing.
+/// * `Query(params)` - Pagination parameters (page, limit).
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the public stickers, newest first, with pagination metadata.
+///
+/// # Errors
+/// Returns an error if the database query fails.
+pub async fn get_public_stickers(
+    State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
+    Extension(fuzzing): Extension<CoordinateFuzzing>,
+    Query(mut params): Query<PaginationParams>,
+    Query(view): Query<StickerViewParams>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!(
+        "GET `/stickers` (public) endpoint called with page={}, limit={}",
+        params.page, params.limit
+    );
+
+    params.validate();
+
+    let query = Stickers::find()
+        .filter(stickers::Column::Visibility.eq(Visibility::Public))
+        .order_by_desc(stickers::Column::CreatedAt);
+
+    #[allow(clippy::cast_possible_truncation)]
+    let total_count = query
+        .clone()
+        .count(&db)
+        .await
+        .context("Failed to count public stickers")? as u32;
+
+    let models = query
+        .offset(params.offset())
+        .limit(params.limit_u64())
+        .all(&db)
+        .await
+        .context("Failed to fetch public stickers from database")?;
+    let stickers = public_sticker_responses(&store, models, fuzzing, view.pictures).await?;
+
+    let metadata = Metadata::paginated(
+        params.page,
+        params.limit,
+        total_count,
+        page_link("/stickers", None, view.pictures),
+    );
+
+    Ok(data_response_with_metadata(
+        json!({
+            "stickers": stickers
+        }),
+        &metadata,
+    ))
+}
+
+/// Handles GET requests to fetch a single public sticker by ID.
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(store)` - Lists the picture variants written to disk.
+/// * `Extension(fuzzing)` - The configured coordinate fuzzing.
+/// * `Path(id)` - The ID of the sticker to fetch.
+/// * `Query(view)` - Shape of the pictures (`pictures=urls` or `pictures=variants`).
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the sticker, with fuzzed coordinates.
+///
+/// # Errors
+/// Returns 404 if the sticker does not exist or is private, 500 if the database query fails.
+pub async fn get_public_sticker(
+    State(db): State<DatabaseConnection>,
+    Extension(store): Extension<PictureStore>,
+    Extension(fuzzing): Extension<CoordinateFuzzing>,
+    Path(id): Path<i32>,
+    Query(view): Query<StickerViewParams>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!("GET `/stickers/{}` (public) endpoint called", id);
+
+    let model = find_sticker(&db, id).await?;
+
+    // Private stickers are reported as missing, not as forbidden
+    if model.visibility != Visibility::Public {
+        return Err(ApiError::not_found(format!(
+            "Sticker with id {id} not found"
+        )));
+    }
+
+    Ok(data_response(json!({
+        "sticker": public_sticker_response(&store, model, fuzzing, view.pictures).await?
+    })))
+}
+
+/// Handles GET requests to fetch the public stickers grouped for a map zoom level.
+///
+/// Clusters are computed from the fuzzed coordinates of public stickers and
+/// cached per zoom level until a sticker changes, see [`cluster`].
+///
+/// # Arguments
+/// * `State(db)` - The database connection.
+/// * `Extension(fuzzing)` - The configured coordinate fuzzing.
+/// * `Extension(clusters)` - The cluster cache.
+/// * `Query(params)` - Zoom level (0-20) and optional viewport (bbox).
+///
+/// # Returns
+/// * `ApiResult<Json<Value>>` - JSON response containing the clusters, each
+///   with its centroid, sticker count and sample sticker ids.
+///
+/// # Errors
+/// Returns 400 listing every invalid parameter, 500 if the database query fails.
+pub async fn get_sticker_clusters(
+    State(db): State<DatabaseConnection>,
+    Extension(fuzzing): Extension<CoordinateFuzzing>,
+    Extension(clusters): Extension<ClusterCache>,
+    Query(params): Query<ClusterParams>,
+) -> ApiResult<Json<serde_json::Value>> {
+    info!(
+        "GET `/stickers/clusters` endpoint called with zoom={}, bbox={:?}",
+        params.zoom, params.bbox
+    );
+
+    let bbox = params.validate()?;
+
+    let level = if let Some(level) = clusters.get(params.zoom) {
+        level
+    } else {
+        // Concurrent misses compute the level once, the others find it cached
+        let _computing = clusters.computing(params.zoom).await;
+        if let Some(level) = clusters.get(params.zoom) {
+            level
+        } else {
+            compute_clusters(&db, fuzzing, &clusters, params.zoom).await?
+        }
+    };
+
+    let visible = level
+        .iter()
+        .filter(|cluster| bbox.is_none_or(|bbox| bbox.intersects(&cluster.cell)))
+        .collect::<Vec<_>>();
+
+    Ok(data_response(json!({
+        "zoom": params.zoom,
+        "clusters": visible
+    })))
+}
+
+/// Clusters the public stickers for a zoom level and caches the result.
+///
+/// # Errors
+/// Returns 500 if the database query fails.
+async fn compute_clusters(
+    db: &DatabaseConnection,
+    fuzzing: CoordinateFuzzing,
+    clusters: &ClusterCache,
+    zoom: u8,
+) -> ApiResult<Arc<Vec<cluster::Cluster>>> {
+    let generation = clusters.generation();
+
+    let points = Stickers::find()
+        .select_only()
+        .columns([
+            stickers::Column::Id,
+            stickers::Column::Latitude,
+            stickers::Column::Longitude,
+        ])
+        .filter(stickers::Column::Visibility.eq(Visibility::Public))
+        .order_by_desc(stickers::Column::CreatedAt)
+        .into_tuple::<(i32, f64, f64)>()
+        .all(db)
+        .await
+        .context("Failed to fetch public stickers from database")?
+        .into_iter()
+        .map(|(id, latitude, longitude)| {
+            let (latitude, longitude) = fuzzing.apply(id, latitude, longitude);
+            Point {
+                id,
+                latitude,
+                longitude,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let level = Arc::new(cluster::cluster(&points, zoom));
+    clusters.insert(zoom, generation, Arc::clone(&level));
+    Ok(level)
+}
+
+/// Lists the pictures of a sticker that are not in its new picture list.
+///
+/// # Errors
+/// Returns 500 if the stored pictures are not a list of strings.
+fn removed_pictures(model: &stickers::Model, pictures: &[String]) -> ApiResult<Vec<String>> {
+    let current: Vec<String> =
+        serde_json::from_value(model.pictures.clone()).context("Failed to parse pictures JSON")?;
+
+    Ok(current
+        .into_iter()
+        .filter(|picture| !pictures.contains(picture))
+        .collect())
+}
+
+/// Fetches every sticker matching the filters, newest first.
+///
+/// # Errors
+/// Returns 400 if a filter is invalid, 500 on database failure.
+async fn find_filtered_stickers(
+    db: &DatabaseConnection,
+    filter: &StickerFilter,
+) -> ApiResult<Vec<stickers::Model>> {
+    Ok(Stickers::find()
+        .filter(filter.condition()?)
+        .order_by_desc(stickers::Column::CreatedAt)
+        .all(db)
+        .await
+        .context("Failed to fetch stickers from database")?)
+}
+
+/// Fetches a sticker by ID.
+///
+/// # Errors
+/// Returns 404 if the sticker is not found, 500 on database failure.
+async fn find_sticker(db: &DatabaseConnection, id: i32) -> ApiResult<stickers::Model> {
+    Stickers::find_by_id(id)
+        .one(db)
+        .await
+        .with_context(|| format!("Failed to fetch sticker with id {id}"))?
+        .ok_or_else(|| ApiError::not_found(format!("Sticker with id {id} not found")))
+}
+
+/// Converts a sticker row into its API representation.
+///
+/// # Errors
+/// Returns 500 if the stored pictures are not a list of strings, or if the
+/// picture variants cannot be listed.
+async fn sticker_response(
+    store: &PictureStore,
+    model: stickers::Model,
+    view: PictureView,
+) -> ApiResult<StickerResponse> {
+    let mut stickers = sticker_responses(store, vec![model], view).await?;
+    Ok(stickers.remove(0))
+}
+
+/// Converts sticker rows into their API representation, in the same order.
+///
+/// With `pictures=variants`, the variants of every picture are looked up on
+/// disk in a single blocking task.
+///
+/// # Errors
+/// Returns 500 if the stored pictures of a sticker are not a list of strings,
+/// or if the picture variants cannot be listed.
+async fn sticker_responses(
+    store: &PictureStore,
+    models: Vec<stickers::Model>,
+    view: PictureView,
+) -> ApiResult<Vec<StickerResponse>> {
+    let pictures = models
+        .iter()
+        .map(|model| serde_json::from_value::<Vec<String>>(model.pictures.clone()))
+        .collect::<Result<Vec<_>, _>>()
+        .context("Failed to parse pictures JSON")?;
+
+    let mut variants = match view {
+        PictureView::Urls => None,
+        PictureView::Variants => Some(store.describe(pictures.concat()).await?.into_iter()),
+    };
+
+    Ok(models
+        .into_iter()
+        .zip(pictures)
+        .map(|(model, pictures)| {
+            let pictures = match &mut variants {
+                None => StickerPictures::Urls(pictures),
+                Some(variants) => {
+                    StickerPictures::Variants(variants.by_ref().take(pictures.len()).collect())
+                }
+            };
+
+            StickerResponse {
+                id: i64::from(model.id),
+                name: model.name,
+                latitude: model.latitude,
+                longitude: model.longitude,
+                place_name: model.place_name,
+                country_code: model.country_code,
+                region: model.region,
+                pictures,
+                visibility: model.visibility,
+                placed_at: model.placed_at.map(|placed_at| placed_at.to_string()),
+                created_at: model.created_at.to_string(),
+                updated_at: model.updated_at.to_string(),
+                distance_km: None,
+            }
+        })
+        .collect())
+}
+
+/// Converts a public sticker row into its API representation, fuzzing its coordinates.
+///
+/// # Errors
+/// Returns 500 if the stored pictures are not a list of strings, or if the
+/// picture variants cannot be listed.
+async fn public_sticker_response(
+    store: &PictureStore,
+    model: stickers::Model,
+    fuzzing: CoordinateFuzzing,
+    view: PictureView,
+) -> ApiResult<StickerResponse> {
+    let mut stickers = public_sticker_responses(store, vec![model], fuzzing, view).await?;
+    Ok(stickers.remove(0))
+}
+
+/// Converts public sticker rows into their API representation, fuzzing their
+/// coordinates.
+///
+/// # Errors
+/// Returns 500 if the stored pictures of a sticker are not a list of strings,
+/// or if the picture variants cannot be listed.
+async fn public_sticker_responses(
+    store: &PictureStore,
+    models: Vec<stickers::Model>,
+    fuzzing: CoordinateFuzzing,
+    view: PictureView,
+) -> ApiResult<Vec<StickerResponse>> {
+    let positions = models
+        .iter()
+        .map(|model| fuzzing.apply(model.id, model.latitude, model.longitude))
+        .collect::<Vec<_>>();
+
+    Ok(sticker_responses(store, models, view)
+        .await?
+        .into_iter()
+        .zip(positions)
+        .map(|(sticker, (latitude, longitude))| StickerResponse {
+            latitude,
+            longitude,
+            ..sticker
+        })
+        .collect())
+}
+
+/// Builds the link of a sticker listing carrying its active query, so the
+/// pagination links keep the filters and the picture shape.
+fn page_link(path: &str, bbox: Option<&str>, pictures: PictureView) -> String {
+    let mut query = url::form_urlencoded::Serializer::new(String::new());
+    if let Some(bbox) = bbox {
+        query.append_pair("bbox", bbox);
+    }
+    if let PictureView::Variants = pictures {
+        query.append_pair("pictures", pictures.as_str());
+    }
+
+    let query = query.finish();
+    if query.is_empty() {
+        path.to_string()
+    } else {
+        format!("{path}?{query}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use sea_orm::ActiveValue;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 1)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn sticker() -> stickers::Model {
+        stickers::Model {
+            id: 1,
+            name: "Pont Neuf".to_string(),
+            latitude: 48.857,
+            longitude: 2.341,
+            place_name: "Paris".to_string(),
+            pictures: json!(["/static/stickers/1/a.jpg", "https://example.org/b.jpg"]),
+            visibility: Visibility::Private,
+            placed_at: Some(at(8)),
+            country_code: Some("FR".to_string()),
+            region: Some("Île-de-France".to_string()),
+            created_at: at(8),
+            updated_at: at(8),
+        }
+    }
+
+    #[test]
+    fn patch_moves_updated_at() {
+        let patch = StickerPatch {
+            name: Some("Pont Marie".to_string()),
+            ..StickerPatch::default()
+        };
+
+        let (sticker, _) = apply_patch(sticker(), patch, at(12)).unwrap();
+
+        assert_eq!(sticker.updated_at, ActiveValue::Set(at(12)));
+        assert_eq!(sticker.created_at, ActiveValue::Unchanged(at(8)));
+        assert_eq!(sticker.name, ActiveValue::Set("Pont Marie".to_string()));
+        assert_eq!(
+            sticker.place_name,
+            ActiveValue::Unchanged("Paris".to_string())
+        );
+    }
+
+    #[test]
+    fn patch_edits_pictures_and_reports_removed_ones() {
+        let patch = StickerPatch {
+            add_pictures: vec!["https://example.org/c.jpg".to_string()],
+            remove_pictures: vec!["/static/stickers/1/a.jpg".to_string()],
+            ..StickerPatch::default()
+        };
+
+        let (sticker, removed) = apply_patch(sticker(), patch, at(12)).unwrap();
+
+        assert_eq!(
+            sticker.pictures,
+            ActiveValue::Set(json!([
+                "https://example.org/b.jpg",
+                "https://example.org/c.jpg"
+            ]))
+        );
+        assert_eq!(removed, ["/static/stickers/1/a.jpg"]);
+    }
+
+    #[test]
+    fn patch_clears_nullable_fields_set_to_null() {
+        let patch: StickerPatch =
+            serde_json::from_value(json!({ "placed_at": null, "region": null })).unwrap();
+
+        let (sticker, _) = apply_patch(sticker(), patch, at(12)).unwrap();
+
+        assert_eq!(sticker.placed_at, ActiveValue::Set(None));
+        assert_eq!(sticker.region, ActiveValue::Set(None));
+        assert_eq!(
+            sticker.country_code,
+            ActiveValue::Unchanged(Some("FR".to_string()))
+        );
+    }
+}
@@ -4,20 +4,87 @@
 //! It includes:
 //! - Data models for requests and responses
 //! - HTTP handlers for CRUD operations
+//! - Map-friendly exports
+//! - Picture uploads and their resized variants
+//! - Placement read from photo EXIF data
+//! - Offline reverse geocoding of place names
+//! - Clustering of public stickers per zoom level
+//! - Validation of sticker input
 //! - Database operations for sticker management
 
+pub mod cluster;
+pub mod export;
+pub mod form;
+pub mod fuzz;
+pub mod geo;
+pub mod geocode;
 pub mod handlers;
 pub mod models;
+pub mod photo;
+pub mod pictures;
+pub mod validate;
+pub mod variants;
 
-use handlers::{create_sticker, get_all_stickers, get_sticker};
+use handlers::{
+    MAX_PICTURES_PER_UPLOAD, create_sticker, delete_sticker, geocode_preview, get_all_stickers,
+    get_nearby_stickers, get_public_sticker, get_public_stickers, get_sticker,
+    get_sticker_clusters, get_stickers_geojson, get_stickers_gpx, get_stickers_kml,
+    replace_sticker, update_sticker, upload_sticker_pictures,
+};
 
-use axum::{Router, routing::get, routing::post};
+use axum::{Router, extract::DefaultBodyLimit, routing::get, routing::post};
 use sea_orm::DatabaseConnection;
 
 /// Creates the sticker router with all endpoints
-pub fn router() -> Router<DatabaseConnection> {
+///
+/// # Arguments
+/// * `max_picture_bytes` - Largest accepted picture, which sizes the body
+///   limit of the routes taking photos.
+pub fn router(max_picture_bytes: usize) -> Router<DatabaseConnection> {
     Router::new()
         .route("/", get(get_all_stickers))
-        .route("/", post(create_sticker))
-        .route("/:id", get(get_sticker))
+        // Stickers can be created from a photo, its size limit is enforced by the handler
+        .route(
+            "/",
+            post(create_sticker).layer(DefaultBodyLimit::max(form::body_limit(
+                1,
+                max_picture_bytes,
+            ))),
+        )
+        .route("/nearby", get(get_nearby_stickers))
+        .route("/geocode", get(geocode_preview))
+        .route(
+            "/:id",
+            get(get_sticker)
+                .put(replace_sticker)
+                .patch(update_sticker)
+                .delete(delete_sticker),
+        )
+        // The picture size limit is enforced per file by the handler
+        .route(
+            "/:id/pictures",
+            post(upload_sticker_pictures).layer(DefaultBodyLimit::max(form::body_limit(
+                MAX_PICTURES_PER_UPLOAD,
+                max_picture_bytes,
+            ))),
+        )
+}
+
+/// Creates the sticker export router, mounted next to the sticker router
+/// since its paths (`/stickers.<format>`) are siblings of `/stickers`.
+pub fn export_router() -> Router<DatabaseConnection> {
+    Router::new()
+        .route("/stickers.geojson", get(get_stickers_geojson))
+        .route("/stickers.gpx", get(get_stickers_gpx))
+        .route("/stickers.kml", get(get_stickers_kml))
+}
+
+/// Creates the public, read-only sticker router.
+///
+/// Only stickers marked `public` are served, with fuzzed coordinates.
+pub fn public_router() -> Router<DatabaseConnection> {
+    Router::new()
+        .route("/", get(get_public_stickers))
+        .route("/clusters", get(get_sticker_clusters))
+        .route("/:id", get(get_public_sticker))
 }
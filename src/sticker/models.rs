@@ -1,6 +1,9 @@
 //! Sticker data models and request/response types
 
-use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::entities::sea_orm_active_enums::Visibility;
 
 /// Request payload for creating a new sticker
 #[derive(Debug, Deserialize, Serialize)]
@@ -8,9 +11,61 @@ pub struct StickerRequest {
     pub name: String,
     pub latitude: f64,
     pub longitude: f64,
+    /// Filled by reverse geocoding when omitted or empty
+    #[serde(default)]
     pub place_name: String,
+    /// ISO 3166-1 alpha-2 code, filled by reverse geocoding when omitted
+    #[serde(default)]
+    pub country_code: Option<String>,
+    /// First-level administrative region, filled by reverse geocoding when omitted
+    #[serde(default)]
+    pub region: Option<String>,
     #[serde(default)]
     pub pictures: Vec<String>,
+    /// Whether the sticker appears in the public feed, private by default
+    #[serde(default)]
+    pub visibility: Visibility,
+    /// When the sticker was placed, in UTC
+    #[serde(default)]
+    pub placed_at: Option<NaiveDateTime>,
+}
+
+/// Request payload for partially updating a sticker, every field is optional
+///
+/// Nullable fields are `None` when absent and `Some(None)` when set to `null`,
+/// which clears them.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct StickerPatch {
+    pub name: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    pub place_name: Option<String>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub country_code: Option<Option<String>>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub region: Option<Option<String>>,
+    /// Replaces the whole picture list
+    pub pictures: Option<Vec<String>>,
+    /// Pictures appended to the list, skipping those already present
+    #[serde(default)]
+    pub add_pictures: Vec<String>,
+    /// Pictures removed from the list
+    #[serde(default)]
+    pub remove_pictures: Vec<String>,
+    pub visibility: Option<Visibility>,
+    #[serde(default, deserialize_with = "nullable")]
+    pub placed_at: Option<Option<NaiveDateTime>>,
+}
+
+/// Deserialize a present field, `null` included, as `Some`.
+///
+/// Combined with `#[serde(default)]`, an absent field stays `None`.
+fn nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
 }
 
 /// Response structure for sticker data
@@ -21,7 +76,108 @@ pub struct StickerResponse {
     pub latitude: f64,
     pub longitude: f64,
     pub place_name: String,
-    pub pictures: Vec<String>,
+    pub country_code: Option<String>,
+    pub region: Option<String>,
+    pub pictures: StickerPictures,
+    pub visibility: Visibility,
+    pub placed_at: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Distance from the search center, only set by the nearby search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distance_km: Option<f64>,
+}
+
+/// Pictures of a sticker, in the shape requested by [`PictureView`]
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum StickerPictures {
+    Urls(Vec<String>),
+    Variants(Vec<PictureResponse>),
+}
+
+/// A picture with its resized variants
+#[derive(Debug, Serialize)]
+pub struct PictureResponse {
+    /// The picture as stored
+    pub original: String,
+    /// Small preview, only for uploaded pictures whose variants are written
+    pub thumb: Option<String>,
+    /// `srcset` attribute listing the width variants narrower than the original
+    pub srcset: Option<String>,
+}
+
+/// Shape of the pictures in sticker responses
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PictureView {
+    /// List of picture paths and URLs
+    #[default]
+    Urls,
+    /// List of [`PictureResponse`] objects
+    Variants,
+}
+
+impl PictureView {
+    /// The `pictures` query parameter value
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Urls => "urls",
+            Self::Variants => "variants",
+        }
+    }
+}
+
+/// Query parameters selecting the shape of sticker responses
+#[derive(Debug, Default, Deserialize)]
+pub struct StickerViewParams {
+    #[serde(default)]
+    pub pictures: PictureView,
+}
+
+/// Filters of the sticker listing
+#[derive(Debug, Default, Deserialize)]
+pub struct StickerFilter {
+    /// Viewport as `minLon,minLat,maxLon,maxLat`
+    pub bbox: Option<String>,
+}
+
+/// Query parameters of the public sticker clusters
+#[derive(Debug, Deserialize)]
+pub struct ClusterParams {
+    /// Map zoom level, from 0 to 20
+    pub zoom: u8,
+    /// Viewport as `minLon,minLat,maxLon,maxLat`
+    pub bbox: Option<String>,
+}
+
+/// Query parameters of the reverse geocoding preview
+#[derive(Debug, Deserialize)]
+pub struct GeocodeParams {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Query parameters of the nearby search
+#[derive(Debug, Deserialize)]
+pub struct NearbyParams {
+    /// Latitude of the search center
+    pub lat: f64,
+    /// Longitude of the search center
+    pub lon: f64,
+    /// Search radius in kilometers
+    #[serde(default = "default_radius_km")]
+    pub radius_km: f64,
+    /// Maximum number of stickers returned
+    #[serde(default = "default_nearby_limit")]
+    pub limit: u64,
+}
+
+const fn default_radius_km() -> f64 {
+    10.0
+}
+
+const fn default_nearby_limit() -> u64 {
+    20
 }
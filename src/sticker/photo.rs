@@ -0,0 +1,251 @@
+//! Placement details read from photo EXIF data
+//!
+//! Geotagged photos carry the position they were taken at in their GPS tags,
+//! and their capture time either in the GPS tags (UTC) or in
+//! `DateTimeOriginal`, in the camera's local time unless `OffsetTimeOriginal`
+//! gives its UTC offset. A local time without offset cannot be placed in UTC,
+//! so it is ignored.
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
+use exif::{Exif, In, Reader, Tag, Value};
+use std::io::Cursor;
+
+/// Where and when a photo was taken, each read on its own
+#[derive(Debug, Clone)]
+pub struct PhotoPlacement {
+    /// Latitude and longitude, or the reason none could be read: no EXIF
+    /// data, no GPS tags, or an out-of-range position
+    pub position: Result<(f64, f64), String>,
+    /// Capture time in UTC, from the GPS time or an offset `DateTimeOriginal`
+    pub taken_at: Option<NaiveDateTime>,
+}
+
+/// Read the GPS position and capture time of a photo.
+#[must_use]
+pub fn placement(bytes: &[u8]) -> PhotoPlacement {
+    let Ok(exif) = Reader::new().read_from_container(&mut Cursor::new(bytes)) else {
+        return PhotoPlacement {
+            position: Err("photo has no EXIF data".to_string()),
+            taken_at: None,
+        };
+    };
+
+    PhotoPlacement {
+        position: position(&exif),
+        taken_at: gps_time(&exif).or_else(|| original_time(&exif)),
+    }
+}
+
+/// Read the GPS position of a photo.
+fn position(exif: &Exif) -> Result<(f64, f64), String> {
+    let latitude = coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, b'S')
+        .ok_or("photo has no GPS position")?;
+    let longitude = coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, b'W')
+        .ok_or("photo has no GPS position")?;
+
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return Err(format!(
+            "photo has an invalid GPS position ({latitude}, {longitude})"
+        ));
+    }
+
+    Ok((latitude, longitude))
+}
+
+/// Read a GPS coordinate stored as degrees, minutes and seconds.
+///
+/// `negative` is the reference (`S` or `W`) that makes the coordinate negative.
+fn coordinate(exif: &Exif, tag: Tag, reference: Tag, negative: u8) -> Option<f64> {
+    let Value::Rational(parts) = &exif.get_field(tag, In::PRIMARY)?.value else {
+        return None;
+    };
+    let [degrees, minutes, seconds] = parts.as_slice() else {
+        return None;
+    };
+    let value = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+    if !value.is_finite() {
+        return None;
+    }
+
+    let reference = match &exif.get_field(reference, In::PRIMARY)?.value {
+        Value::Ascii(values) => values.first()?.first().copied()?,
+        _ => return None,
+    };
+
+    Some(if reference == negative { -value } else { value })
+}
+
+/// Capture time from `GPSDateStamp` and `GPSTimeStamp`, always UTC.
+fn gps_time(exif: &Exif) -> Option<NaiveDateTime> {
+    let date = match &exif.get_field(Tag::GPSDateStamp, In::PRIMARY)?.value {
+        Value::Ascii(values) => std::str::from_utf8(values.first()?).ok()?.to_string(),
+        _ => return None,
+    };
+    let date = NaiveDate::parse_from_str(date.trim_end_matches('\0'), "%Y:%m:%d").ok()?;
+
+    let Value::Rational(parts) = &exif.get_field(Tag::GPSTimeStamp, In::PRIMARY)?.value else {
+        return None;
+    };
+    let [hour, minute, second] = parts.as_slice() else {
+        return None;
+    };
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let seconds =
+        (hour.to_f64() * 3600.0 + minute.to_f64() * 60.0 + second.to_f64()).floor() as u32;
+
+    Some(date.and_time(NaiveTime::from_num_seconds_from_midnight_opt(seconds, 0)?))
+}
+
+/// Capture time from `DateTimeOriginal`, converted to UTC with
+/// `OffsetTimeOriginal`. `None` without a valid offset, the time is then local.
+fn original_time(exif: &Exif) -> Option<NaiveDateTime> {
+    let Value::Ascii(values) = &exif.get_field(Tag::DateTimeOriginal, In::PRIMARY)?.value else {
+        return None;
+    };
+    let mut time = exif::DateTime::from_ascii(values.first()?).ok()?;
+
+    let Value::Ascii(offset) = &exif.get_field(Tag::OffsetTimeOriginal, In::PRIMARY)?.value else {
+        return None;
+    };
+    time.parse_offset(offset.first()?).ok()?;
+    let minutes = time.offset?;
+
+    let local = NaiveDate::from_ymd_opt(time.year.into(), time.month.into(), time.day.into())?
+        .and_hms_opt(time.hour.into(), time.minute.into(), time.second.into())?;
+
+    Some(local - TimeDelta::minutes(minutes.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exif::{Field, Rational, experimental::Writer};
+
+    fn ascii(tag: Tag, value: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Ascii(vec![value.as_bytes().to_vec()]),
+        }
+    }
+
+    fn rationals(tag: Tag, values: [u32; 3]) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: Value::Rational(values.map(|n| Rational::from((n, 1))).to_vec()),
+        }
+    }
+
+    /// A TIFF block holding the given EXIF fields.
+    fn tiff(fields: &[Field]) -> Vec<u8> {
+        let mut writer = Writer::new();
+        for field in fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        tiff.into_inner()
+    }
+
+    /// GPS fields of 33°51'30" and 151°12'36", on the given hemispheres.
+    fn gps(latitude_ref: &str, longitude_ref: &str) -> [Field; 4] {
+        [
+            ascii(Tag::GPSLatitudeRef, latitude_ref),
+            rationals(Tag::GPSLatitude, [33, 51, 30]),
+            ascii(Tag::GPSLongitudeRef, longitude_ref),
+            rationals(Tag::GPSLongitude, [151, 12, 36]),
+        ]
+    }
+
+    fn at(date: (i32, u32, u32), time: (u32, u32, u32)) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(date.0, date.1, date.2)
+            .unwrap()
+            .and_hms_opt(time.0, time.1, time.2)
+            .unwrap()
+    }
+
+    #[test]
+    fn position_sign_follows_the_hemisphere_references() {
+        let (latitude, longitude) = (33.0 + 51.0 / 60.0 + 30.0 / 3600.0, 151.21);
+
+        for (latitude_ref, longitude_ref, expected) in [
+            ("N", "E", (latitude, longitude)),
+            ("S", "E", (-latitude, longitude)),
+            ("N", "W", (latitude, -longitude)),
+            ("S", "W", (-latitude, -longitude)),
+        ] {
+            let placement = placement(&tiff(&gps(latitude_ref, longitude_ref)));
+            let (lat, lon) = placement.position.unwrap();
+
+            assert!((lat - expected.0).abs() < 1e-9, "{latitude_ref}: {lat}");
+            assert!((lon - expected.1).abs() < 1e-9, "{longitude_ref}: {lon}");
+        }
+    }
+
+    #[test]
+    fn missing_gps_tags_give_no_position() {
+        let without_gps = placement(&tiff(&[ascii(Tag::Make, "Camera")]));
+        assert_eq!(
+            without_gps.position.unwrap_err(),
+            "photo has no GPS position"
+        );
+
+        // A latitude without its reference cannot be signed
+        let [_, latitude, longitude_ref, longitude] = gps("S", "E");
+        let without_ref = placement(&tiff(&[latitude, longitude_ref, longitude]));
+        assert!(without_ref.position.is_err());
+
+        let without_exif = placement(b"not a photo");
+        assert_eq!(without_exif.position.unwrap_err(), "photo has no EXIF data");
+        assert_eq!(without_exif.taken_at, None);
+    }
+
+    #[test]
+    fn out_of_range_position_is_refused() {
+        let fields = [
+            ascii(Tag::GPSLatitudeRef, "N"),
+            rationals(Tag::GPSLatitude, [95, 0, 0]),
+            ascii(Tag::GPSLongitudeRef, "E"),
+            rationals(Tag::GPSLongitude, [10, 0, 0]),
+        ];
+
+        let error = placement(&tiff(&fields)).position.unwrap_err();
+        assert!(error.contains("invalid GPS position"), "{error}");
+    }
+
+    #[test]
+    fn original_time_with_offset_is_converted_to_utc() {
+        let fields = [
+            ascii(Tag::DateTimeOriginal, "2026:07:14 09:30:00"),
+            ascii(Tag::OffsetTimeOriginal, "+02:00"),
+        ];
+
+        let placement = placement(&tiff(&fields));
+
+        assert_eq!(placement.taken_at, Some(at((2026, 7, 14), (7, 30, 0))));
+        assert!(placement.position.is_err());
+    }
+
+    #[test]
+    fn original_time_without_offset_is_ignored() {
+        let fields = [ascii(Tag::DateTimeOriginal, "2026:07:14 09:30:00")];
+
+        assert_eq!(placement(&tiff(&fields)).taken_at, None);
+    }
+
+    #[test]
+    fn gps_time_is_preferred_over_original_time() {
+        let fields = [
+            ascii(Tag::GPSDateStamp, "2026:07:13"),
+            rationals(Tag::GPSTimeStamp, [23, 5, 9]),
+            ascii(Tag::DateTimeOriginal, "2026:07:14 09:30:00"),
+            ascii(Tag::OffsetTimeOriginal, "+10:00"),
+        ];
+
+        assert_eq!(
+            placement(&tiff(&fields)).taken_at,
+            Some(at((2026, 7, 13), (23, 5, 9)))
+        );
+    }
+}
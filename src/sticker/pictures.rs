@@ -0,0 +1,647 @@
+//! Sticker picture uploads
+//!
+//! Uploaded pictures are sniffed from their content (the declared content
+//! type is ignored), limited in size and stripped of their metadata before
+//! being written under `STATIC_DIR/stickers/<id>/`, named after the SHA-256
+//! of their content so identical uploads are stored once.
+//!
+//! Metadata (EXIF, XMP, IPTC, vendor segments, text chunks) is removed
+//! losslessly by dropping the container segments that carry it. Pictures whose EXIF orientation is
+//! not the default are decoded, rotated and re-encoded instead, so they still
+//! display upright once the orientation tag is gone.
+//!
+//! Resized variants are generated next to each picture, see [`variants`](super::variants).
+//!
+//! Files follow their sticker: pictures dropped from a sticker are deleted
+//! with their variants, and deleting a sticker deletes its directory.
+
+use anyhow::Context;
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, metadata::Orientation};
+use sha2::{Digest, Sha256};
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+};
+
+use super::models::PictureResponse;
+
+/// Public URL prefix of files served from `STATIC_DIR`
+const STATIC_URL: &str = "/static";
+
+/// Where and how sticker pictures are stored.
+#[derive(Debug, Clone)]
+pub struct PictureStore {
+    static_dir: String,
+    max_bytes: usize,
+}
+
+/// A picture ready to be written
+#[derive(Debug)]
+pub struct ProcessedPicture {
+    pub bytes: Vec<u8>,
+    pub format: ImageFormat,
+}
+
+impl PictureStore {
+    /// Create a store writing below `static_dir`.
+    ///
+    /// # Arguments
+    /// * `static_dir` - The directory served under `/static`.
+    /// * `max_bytes` - Largest accepted upload, per picture.
+    #[must_use]
+    pub const fn new(static_dir: String, max_bytes: usize) -> Self {
+        Self {
+            static_dir,
+            max_bytes,
+        }
+    }
+
+    /// Largest accepted upload, per picture.
+    #[must_use]
+    pub const fn max_bytes(&self) -> usize {
+        self.max_bytes
+    }
+
+    /// Generate the missing variants of every stored picture, in the background.
+    pub fn spawn_variant_backfill(&self) {
+        let static_dir = self.static_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            if let Err(e) = super::variants::backfill(&static_dir) {
+                tracing::error!("Picture variant backfill failed: {e:#}");
+            }
+        });
+    }
+
+    /// Describe stored pictures with the variants written next to them, in
+    /// the same order.
+    ///
+    /// Variants are looked up on disk on a blocking thread.
+    ///
+    /// # Errors
+    /// Returns an error if the blocking task fails.
+    pub async fn describe(&self, pictures: Vec<String>) -> anyhow::Result<Vec<PictureResponse>> {
+        if pictures.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let static_dir = self.static_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            pictures
+                .iter()
+                .map(|picture| super::variants::describe(Path::new(&static_dir), picture))
+                .collect()
+        })
+        .await
+        .context("Picture description task failed")
+    }
+
+    /// [`Self::process`] on a blocking thread, as a rotated picture is decoded
+    /// and re-encoded.
+    ///
+    /// # Errors
+    /// Returns an error if the blocking task fails. The inner result holds the
+    /// reason the upload is refused.
+    pub async fn process_blocking(
+        &self,
+        bytes: Vec<u8>,
+    ) -> anyhow::Result<Result<ProcessedPicture, String>> {
+        let store = self.clone();
+        tokio::task::spawn_blocking(move || store.process(&bytes))
+            .await
+            .context("Picture processing task failed")
+    }
+
+    /// Check an upload and strip its metadata.
+    ///
+    /// # Errors
+    /// Returns the reason the upload is refused: too large, not a JPEG, PNG
+    /// or WebP image, or malformed.
+    pub fn process(&self, bytes: &[u8]) -> Result<ProcessedPicture, String> {
+        if bytes.len() > self.max_bytes {
+            return Err(format!("picture exceeds {} bytes", self.max_bytes));
+        }
+
+        let reader = ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .map_err(|e| format!("could not read picture: {e}"))?;
+
+        let format = match reader.format() {
+            Some(format @ (ImageFormat::Jpeg | ImageFormat::Png | ImageFormat::WebP)) => format,
+            _ => return Err("picture must be a JPEG, PNG or WebP image".to_string()),
+        };
+
+        let mut decoder = reader
+            .into_decoder()
+            .map_err(|e| format!("malformed {format:?} picture: {e}"))?;
+
+        let orientation = decoder.orientation().unwrap_or(Orientation::NoTransforms);
+        if orientation == Orientation::NoTransforms {
+            let bytes = strip_metadata(format, bytes)?;
+            return Ok(ProcessedPicture { bytes, format });
+        }
+
+        let mut image = DynamicImage::from_decoder(decoder)
+            .map_err(|e| format!("malformed {format:?} picture: {e}"))?;
+        image.apply_orientation(orientation);
+
+        let mut encoded = Cursor::new(Vec::new());
+        image
+            .write_to(&mut encoded, format)
+            .map_err(|e| format!("could not re-encode picture: {e}"))?;
+
+        Ok(ProcessedPicture {
+            bytes: encoded.into_inner(),
+            format,
+        })
+    }
+
+    /// The public `/static/...` path a processed picture of a sticker is stored at.
+    #[must_use]
+    pub fn url(sticker_id: i32, picture: &ProcessedPicture) -> String {
+        format!("{STATIC_URL}/{}", Self::relative_path(sticker_id, picture))
+    }
+
+    /// Write a processed picture of a sticker, unless an identical one exists,
+    /// and its missing variants.
+    ///
+    /// # Returns
+    /// The public `/static/...` path of the picture.
+    ///
+    /// # Errors
+    /// Returns an error if the directory, the file or a variant cannot be written.
+    pub async fn save(
+        &self,
+        sticker_id: i32,
+        picture: &ProcessedPicture,
+    ) -> anyhow::Result<String> {
+        let relative = Self::relative_path(sticker_id, picture);
+
+        let path = Path::new(&self.static_dir).join(&relative);
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+
+        if !tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            tokio::fs::write(&path, &picture.bytes)
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+
+        tokio::task::spawn_blocking(move || super::variants::generate(&path))
+            .await
+            .context("Picture variant task failed")??;
+
+        Ok(format!("{STATIC_URL}/{relative}"))
+    }
+
+    /// Delete stored pictures of a sticker and their variants.
+    ///
+    /// Pictures that are not files of this sticker, such as external URLs,
+    /// are left alone. Failures are logged, the database no longer refers to
+    /// the files at this point.
+    pub async fn remove(&self, sticker_id: i32, pictures: &[String]) {
+        let prefix = format!("{STATIC_URL}/stickers/{sticker_id}/");
+        let directory = self.sticker_dir(sticker_id);
+        let originals = pictures
+            .iter()
+            .filter_map(|picture| picture.strip_prefix(&prefix))
+            .filter(|name| !name.contains('/'))
+            .map(|name| directory.join(name))
+            .collect::<Vec<_>>();
+        if originals.is_empty() {
+            return;
+        }
+
+        let removed = tokio::task::spawn_blocking(move || {
+            for original in &originals {
+                if let Err(e) = super::variants::remove(original) {
+                    tracing::warn!("Failed to remove picture {}: {e:#}", original.display());
+                }
+            }
+            // Only succeeds once the directory is empty
+            let _ = std::fs::remove_dir(&directory);
+        })
+        .await;
+
+        if let Err(e) = removed {
+            tracing::warn!("Picture removal task failed: {e}");
+        }
+    }
+
+    /// Delete every stored picture of a sticker. Failures are logged.
+    pub async fn remove_all(&self, sticker_id: i32) {
+        let directory = self.sticker_dir(sticker_id);
+        match tokio::fs::remove_dir_all(&directory).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Failed to remove {}: {e}", directory.display()),
+        }
+    }
+
+    /// Directory holding the pictures of a sticker.
+    fn sticker_dir(&self, sticker_id: i32) -> PathBuf {
+        Path::new(&self.static_dir)
+            .join("stickers")
+            .join(sticker_id.to_string())
+    }
+
+    /// Path of a picture below `STATIC_DIR`, named after its content hash.
+    fn relative_path(sticker_id: i32, picture: &ProcessedPicture) -> String {
+        let hash = hex::encode(Sha256::digest(&picture.bytes));
+        let extension = picture.format.extensions_str()[0];
+        format!("stickers/{sticker_id}/{}.{extension}", &hash[..32])
+    }
+}
+
+/// Remove metadata segments from an image without re-encoding it.
+fn strip_metadata(format: ImageFormat, bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let stripped = match format {
+        ImageFormat::Jpeg => strip_jpeg(bytes),
+        ImageFormat::Png => strip_png(bytes),
+        ImageFormat::WebP => strip_webp(bytes),
+        _ => return Err(format!("unsupported picture format {format:?}")),
+    };
+    stripped.ok_or_else(|| format!("malformed {format:?} picture"))
+}
+
+/// Drop the application segments of a JPEG other than APP0 JFIF, APP2 ICC
+/// profiles and APP14 Adobe, its comments, and everything after its end marker.
+///
+/// Application segments carry EXIF, XMP, IPTC, MPF and vendor metadata; the
+/// kept ones only describe how to decode the picture and its colors. Data after
+/// EOI holds MPF secondary images (gain or depth maps, with their own EXIF) or
+/// motion-photo videos, so the output ends at EOI.
+fn strip_jpeg(bytes: &[u8]) -> Option<Vec<u8>> {
+    const APP0: u8 = 0xE0;
+    const APP2: u8 = 0xE2;
+    const APP14: u8 = 0xEE;
+    const APP15: u8 = 0xEF;
+    const COM: u8 = 0xFE;
+    const SOS: u8 = 0xDA;
+    const EOI: u8 = 0xD9;
+
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(bytes.get(..2).filter(|soi| *soi == [0xFF, 0xD8])?);
+
+    let mut i = 2;
+    loop {
+        if *bytes.get(i)? != 0xFF {
+            return None;
+        }
+        let marker = *bytes.get(i + 1)?;
+
+        match marker {
+            // Fill byte before a marker
+            0xFF => i += 1,
+            EOI => {
+                out.extend_from_slice(&bytes[i..i + 2]);
+                return Some(out);
+            }
+            // Markers without a length
+            0x01 | 0xD0..=0xD7 => {
+                out.extend_from_slice(&bytes[i..i + 2]);
+                i += 2;
+            }
+            _ => {
+                let length =
+                    usize::from(u16::from_be_bytes([*bytes.get(i + 2)?, *bytes.get(i + 3)?]));
+                let end = i + 2 + length;
+                let segment = bytes.get(i..end)?;
+                let identifier = segment.get(4..).unwrap_or_default();
+                let metadata = match marker {
+                    APP0 => !identifier.starts_with(b"JFIF\0"),
+                    APP2 => !identifier.starts_with(b"ICC_PROFILE\0"),
+                    APP14 => !identifier.starts_with(b"Adobe"),
+                    APP0..=APP15 | COM => true,
+                    _ => false,
+                };
+                if !metadata {
+                    out.extend_from_slice(segment);
+                }
+                i = end;
+
+                // Entropy-coded data follows a scan header, up to the next marker
+                if marker == SOS {
+                    let start = i;
+                    i = scan_end(bytes, start)?;
+                    out.extend_from_slice(&bytes[start..i]);
+                }
+            }
+        }
+    }
+}
+
+/// Find the marker ending the entropy-coded data of a JPEG scan.
+///
+/// Inside the data, `FF 00` is an escaped byte and `FF D0`-`FF D7` are
+/// restart markers.
+fn scan_end(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut i = start;
+    loop {
+        i += bytes.get(i..)?.iter().position(|byte| *byte == 0xFF)?;
+
+        match *bytes.get(i + 1)? {
+            0x00 | 0xD0..=0xD7 => i += 2,
+            _ => return Some(i),
+        }
+    }
+}
+
+/// Drop the `eXIf`, text and `tIME` chunks of a PNG, and anything after `IEND`.
+fn strip_png(bytes: &[u8]) -> Option<Vec<u8>> {
+    const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(bytes.get(..8).filter(|signature| *signature == SIGNATURE)?);
+
+    let mut i = 8;
+    loop {
+        let length = u32::from_be_bytes(bytes.get(i..i + 4)?.try_into().ok()?);
+        let kind = bytes.get(i + 4..i + 8)?;
+        // Length, type, data and CRC
+        let end = i + 12 + usize::try_from(length).ok()?;
+        let chunk = bytes.get(i..end)?;
+
+        if !matches!(kind, b"eXIf" | b"tEXt" | b"zTXt" | b"iTXt" | b"tIME") {
+            out.extend_from_slice(chunk);
+        }
+        if kind == b"IEND" {
+            return Some(out);
+        }
+        i = end;
+    }
+}
+
+/// Drop the `EXIF` and `XMP ` chunks of a WebP and clear their `VP8X` flags.
+///
+/// Data after the RIFF chunk is dropped as well.
+fn strip_webp(bytes: &[u8]) -> Option<Vec<u8>> {
+    const XMP_FLAG: u8 = 0x04;
+    const EXIF_FLAG: u8 = 0x08;
+
+    if bytes.get(..4)? != b"RIFF" || bytes.get(8..12)? != b"WEBP" {
+        return None;
+    }
+
+    // Anything past the RIFF size is not part of the image and is dropped
+    let riff_size = usize::try_from(u32::from_le_bytes(bytes.get(4..8)?.try_into().ok()?)).ok()?;
+    let bytes = bytes.get(..8 + riff_size).unwrap_or(bytes);
+
+    let mut out = Vec::with_capacity(bytes.len());
+    out.extend_from_slice(&bytes[..12]);
+
+    let mut i = 12;
+    while i < bytes.len() {
+        let kind = bytes.get(i..i + 4)?;
+        let size = u32::from_le_bytes(bytes.get(i + 4..i + 8)?.try_into().ok()?);
+        // Chunks are padded to an even size
+        let size = usize::try_from(size).ok()?;
+        let end = (i + 8 + size + (size & 1)).min(bytes.len());
+        let chunk = bytes.get(i..end)?;
+
+        match kind {
+            b"EXIF" | b"XMP " => {}
+            b"VP8X" => {
+                let start = out.len();
+                out.extend_from_slice(chunk);
+                *out.get_mut(start + 8)? &= !(XMP_FLAG | EXIF_FLAG);
+            }
+            _ => out.extend_from_slice(chunk),
+        }
+        i = end;
+    }
+
+    // The RIFF size counts everything after the size field
+    let riff_size = u32::try_from(out.len() - 8).ok()?;
+    out[4..8].copy_from_slice(&riff_size.to_le_bytes());
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sticker::photo;
+    use exif::{Field, In, Tag, Value, experimental::Writer};
+    use image::{Rgb, RgbImage};
+
+    /// Data found after the image in motion photos
+    const TRAILER: &[u8] = b"\0\0\0\x18ftypmp42motion-photo-video";
+
+    /// A TIFF block with a GPS position, as carried by EXIF metadata.
+    fn gps_tiff() -> Vec<u8> {
+        let fields = [
+            Field {
+                tag: Tag::GPSLatitudeRef,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"N".to_vec()]),
+            },
+            Field {
+                tag: Tag::GPSLatitude,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![(48, 1).into(), (51, 1).into(), (30, 1).into()]),
+            },
+            Field {
+                tag: Tag::GPSLongitudeRef,
+                ifd_num: In::PRIMARY,
+                value: Value::Ascii(vec![b"E".to_vec()]),
+            },
+            Field {
+                tag: Tag::GPSLongitude,
+                ifd_num: In::PRIMARY,
+                value: Value::Rational(vec![(2, 1).into(), (17, 1).into(), (40, 1).into()]),
+            },
+        ];
+
+        let mut writer = Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        tiff.into_inner()
+    }
+
+    /// A small picture encoded without metadata.
+    fn encode(format: ImageFormat) -> Vec<u8> {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(16, 16, Rgb([200, 40, 40])));
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    /// A JPEG with a GPS APP1 segment right after SOI.
+    fn geotagged_jpeg(tiff: &[u8]) -> Vec<u8> {
+        let jpeg = encode(ImageFormat::Jpeg);
+        let payload = [b"Exif\0\0".as_slice(), tiff].concat();
+        let length = u16::try_from(payload.len() + 2).unwrap();
+
+        let mut bytes = vec![0xFF, 0xD8, 0xFF, 0xE1];
+        bytes.extend_from_slice(&length.to_be_bytes());
+        bytes.extend_from_slice(&payload);
+        bytes.extend_from_slice(&jpeg[2..]);
+        bytes
+    }
+
+    /// A RIFF chunk, padded to an even size.
+    fn chunk(kind: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut bytes = kind.to_vec();
+        bytes.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+        bytes.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+    }
+
+    /// Check that the GPS metadata and the trailer are gone and the picture still decodes.
+    fn assert_stripped(original: &[u8], stripped: &[u8], tiff: &[u8]) {
+        assert!(photo::placement(original).position.is_ok());
+        assert!(contains(original, tiff) && contains(original, TRAILER));
+
+        assert!(!contains(stripped, tiff));
+        assert!(!contains(stripped, TRAILER));
+        assert!(photo::placement(stripped).position.is_err());
+        image::load_from_memory(stripped).unwrap();
+    }
+
+    #[test]
+    fn strip_jpeg_drops_gps_and_trailing_data() {
+        let tiff = gps_tiff();
+        let mut bytes = geotagged_jpeg(&tiff);
+        // MPF secondary image with its own EXIF, then a motion-photo video
+        bytes.extend_from_slice(&geotagged_jpeg(&tiff));
+        bytes.extend_from_slice(TRAILER);
+
+        let stripped = strip_jpeg(&bytes).unwrap();
+
+        assert_stripped(&bytes, &stripped, &tiff);
+        assert!(stripped.ends_with(&[0xFF, 0xD9]));
+        assert_eq!(
+            stripped.windows(2).filter(|w| *w == [0xFF, 0xD8]).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn strip_jpeg_keeps_only_decoding_segments() {
+        let segment = |marker: u8, payload: &[u8]| {
+            let mut bytes = vec![0xFF, marker];
+            bytes.extend_from_slice(&u16::try_from(payload.len() + 2).unwrap().to_be_bytes());
+            bytes.extend_from_slice(payload);
+            bytes
+        };
+        let kept = [
+            segment(0xE2, b"ICC_PROFILE\0\x01\x01profile"),
+            segment(0xEE, b"Adobe\0\x64\0\0\0\0\x01"),
+        ];
+        let dropped = [
+            segment(0xE0, b"JFXX\0\x13thumbnail"),
+            segment(0xE2, b"FPXR\0flashpix"),
+            segment(0xE3, b"Meta\0vendor serial 1234"),
+            segment(0xEC, b"Ducky\0\x01quality"),
+            segment(0xEF, b"vendor notes"),
+            segment(0xFE, b"shot by someone"),
+        ];
+
+        let jpeg = encode(ImageFormat::Jpeg);
+        let mut bytes = jpeg[..2].to_vec();
+        for extra in kept.iter().chain(&dropped) {
+            bytes.extend_from_slice(extra);
+        }
+        bytes.extend_from_slice(&jpeg[2..]);
+
+        let stripped = strip_jpeg(&bytes).unwrap();
+
+        assert!(contains(&stripped, b"JFIF\0"));
+        for segment in &kept {
+            assert!(contains(&stripped, segment));
+        }
+        for segment in &dropped {
+            assert!(!contains(&stripped, segment));
+        }
+        assert_eq!(
+            stripped.len(),
+            jpeg.len() + kept.iter().map(Vec::len).sum::<usize>()
+        );
+        image::load_from_memory(&stripped).unwrap();
+    }
+
+    #[test]
+    fn strip_png_drops_gps_and_trailing_data() {
+        const SIGNATURE_LENGTH: usize = 8;
+
+        let tiff = gps_tiff();
+        let png = encode(ImageFormat::Png);
+
+        let mut exif = u32::try_from(tiff.len()).unwrap().to_be_bytes().to_vec();
+        exif.extend_from_slice(b"eXIf");
+        exif.extend_from_slice(&tiff);
+        exif.extend_from_slice(&crc32(&exif[4..]).to_be_bytes());
+
+        // The eXIf chunk goes right after IHDR (length, type, 13 bytes of data and CRC)
+        let ihdr_end = SIGNATURE_LENGTH + 25;
+        let mut bytes = png[..ihdr_end].to_vec();
+        bytes.extend_from_slice(&exif);
+        bytes.extend_from_slice(&png[ihdr_end..]);
+        bytes.extend_from_slice(TRAILER);
+
+        let stripped = strip_png(&bytes).unwrap();
+
+        assert_stripped(&bytes, &stripped, &tiff);
+        assert_eq!(stripped, png);
+    }
+
+    #[test]
+    fn strip_webp_drops_gps_and_trailing_data() {
+        const EXIF_FLAG: u8 = 0x08;
+
+        let tiff = gps_tiff();
+        let webp = encode(ImageFormat::WebP);
+
+        // Extended format header: flags, then canvas width and height minus one
+        let mut vp8x = vec![EXIF_FLAG, 0, 0, 0];
+        vp8x.extend_from_slice(&15u32.to_le_bytes()[..3]);
+        vp8x.extend_from_slice(&15u32.to_le_bytes()[..3]);
+
+        let mut body = b"WEBP".to_vec();
+        body.extend_from_slice(&chunk(b"VP8X", &vp8x));
+        body.extend_from_slice(&webp[12..]);
+        body.extend_from_slice(&chunk(b"EXIF", &tiff));
+
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&u32::try_from(body.len()).unwrap().to_le_bytes());
+        bytes.extend_from_slice(&body);
+        bytes.extend_from_slice(TRAILER);
+
+        let stripped = strip_webp(&bytes).unwrap();
+
+        assert_stripped(&bytes, &stripped, &tiff);
+        assert_eq!(stripped[20] & EXIF_FLAG, 0);
+        let riff_size = u32::from_le_bytes(stripped[4..8].try_into().unwrap());
+        assert_eq!(usize::try_from(riff_size).unwrap(), stripped.len() - 8);
+    }
+
+    /// CRC-32 of a PNG chunk type and data.
+    fn crc32(bytes: &[u8]) -> u32 {
+        let mut crc = u32::MAX;
+        for byte in bytes {
+            crc ^= u32::from(*byte);
+            for _ in 0..8 {
+                crc = if crc & 1 == 1 {
+                    (crc >> 1) ^ 0xEDB8_8320
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+        !crc
+    }
+}
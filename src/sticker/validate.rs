@@ -0,0 +1,382 @@
+//! Sticker input validation
+//!
+//! Every field is checked and all failures are reported together, so a client
+//! can fix its payload in one round trip.
+
+use sea_orm::Condition;
+
+use super::{
+    cluster::MAX_ZOOM,
+    geo::GeoBox,
+    models::{
+        ClusterParams, GeocodeParams, NearbyParams, StickerFilter, StickerPatch, StickerRequest,
+    },
+};
+use crate::error::{ApiError, ApiResult, FieldError};
+
+/// Prefix of pictures served from `STATIC_DIR`
+const STATIC_PREFIX: &str = "/static/";
+
+/// Largest nearby search radius, half the Earth's circumference
+pub const MAX_RADIUS_KM: f64 = 20_000.0;
+
+/// Largest number of stickers a nearby search returns
+pub const MAX_NEARBY_LIMIT: u64 = 100;
+
+impl StickerRequest {
+    /// Validate every field of the payload.
+    ///
+    /// # Errors
+    /// Returns [`ApiError::InvalidFields`] listing each invalid field.
+    pub fn validate(&self) -> ApiResult<()> {
+        let mut errors = Vec::new();
+
+        check_text(&mut errors, "name", &self.name);
+        check_text(&mut errors, "place_name", &self.place_name);
+        check_place(
+            &mut errors,
+            self.country_code.as_deref(),
+            self.region.as_deref(),
+        );
+        check_latitude(&mut errors, self.latitude);
+        check_longitude(&mut errors, self.longitude);
+        check_pictures(&mut errors, "pictures", &self.pictures);
+
+        into_result(errors)
+    }
+}
+
+impl StickerPatch {
+    /// Validate the fields present in the payload.
+    ///
+    /// # Errors
+    /// Returns [`ApiError::InvalidFields`] listing each invalid field.
+    pub fn validate(&self) -> ApiResult<()> {
+        let mut errors = Vec::new();
+
+        if let Some(name) = &self.name {
+            check_text(&mut errors, "name", name);
+        }
+        if let Some(place_name) = &self.place_name {
+            check_text(&mut errors, "place_name", place_name);
+        }
+        check_place(
+            &mut errors,
+            self.country_code.as_ref().and_then(Option::as_deref),
+            self.region.as_ref().and_then(Option::as_deref),
+        );
+        if let Some(latitude) = self.latitude {
+            check_latitude(&mut errors, latitude);
+        }
+        if let Some(longitude) = self.longitude {
+            check_longitude(&mut errors, longitude);
+        }
+        if let Some(pictures) = &self.pictures {
+            check_pictures(&mut errors, "pictures", pictures);
+        }
+        check_pictures(&mut errors, "add_pictures", &self.add_pictures);
+
+        into_result(errors)
+    }
+}
+
+impl NearbyParams {
+    /// Validate the search center, radius and limit.
+    ///
+    /// # Errors
+    /// Returns [`ApiError::InvalidFields`] listing each invalid parameter.
+    pub fn validate(&self) -> ApiResult<()> {
+        let mut errors = Vec::new();
+
+        check_coordinate(&mut errors, "lat", self.lat, 90.0);
+        check_coordinate(&mut errors, "lon", self.lon, 180.0);
+
+        if !self.radius_km.is_finite() || self.radius_km <= 0.0 || self.radius_km > MAX_RADIUS_KM {
+            errors.push(FieldError::new(
+                "radius_km",
+                format!(
+                    "must be between 0 and {MAX_RADIUS_KM}, got {}",
+                    self.radius_km
+                ),
+            ));
+        }
+
+        if self.limit == 0 || self.limit > MAX_NEARBY_LIMIT {
+            errors.push(FieldError::new(
+                "limit",
+                format!(
+                    "must be between 1 and {MAX_NEARBY_LIMIT}, got {}",
+                    self.limit
+                ),
+            ));
+        }
+
+        into_result(errors)
+    }
+}
+
+impl ClusterParams {
+    /// Validate the zoom level and parse the viewport.
+    ///
+    /// # Errors
+    /// Returns [`ApiError::InvalidFields`] listing each invalid parameter.
+    pub fn validate(&self) -> ApiResult<Option<GeoBox>> {
+        let mut errors = Vec::new();
+
+        if self.zoom > MAX_ZOOM {
+            errors.push(FieldError::new(
+                "zoom",
+                format!("must be between 0 and {MAX_ZOOM}, got {}", self.zoom),
+            ));
+        }
+
+        let bbox = self.bbox.as_deref().and_then(|bbox| {
+            GeoBox::parse_bbox(bbox)
+                .map_err(|message| errors.push(FieldError::new("bbox", message)))
+                .ok()
+        });
+
+        into_result(errors).map(|()| bbox)
+    }
+}
+
+impl GeocodeParams {
+    /// Validate the point to geocode.
+    ///
+    /// # Errors
+    /// Returns [`ApiError::InvalidFields`] listing each invalid parameter.
+    pub fn validate(&self) -> ApiResult<()> {
+        let mut errors = Vec::new();
+
+        check_coordinate(&mut errors, "lat", self.lat, 90.0);
+        check_coordinate(&mut errors, "lon", self.lon, 180.0);
+
+        into_result(errors)
+    }
+}
+
+impl StickerFilter {
+    /// Validate the filters and turn them into a query condition.
+    ///
+    /// # Errors
+    /// Returns [`ApiError::InvalidFields`] listing each invalid filter.
+    pub fn condition(&self) -> ApiResult<Condition> {
+        let mut errors = Vec::new();
+        let mut condition = Condition::all();
+
+        if let Some(bbox) = &self.bbox {
+            match GeoBox::parse_bbox(bbox) {
+                Ok(bbox) => condition = condition.add(bbox.condition()),
+                Err(message) => errors.push(FieldError::new("bbox", message)),
+            }
+        }
+
+        into_result(errors).map(|()| condition)
+    }
+}
+
+fn into_result(errors: Vec<FieldError>) -> ApiResult<()> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ApiError::InvalidFields(errors))
+    }
+}
+
+fn check_text(errors: &mut Vec<FieldError>, field: &str, value: &str) {
+    if value.trim().is_empty() {
+        errors.push(FieldError::new(field, "must not be empty"));
+    }
+}
+
+fn check_place(errors: &mut Vec<FieldError>, country_code: Option<&str>, region: Option<&str>) {
+    if let Some(country_code) = country_code
+        && !(country_code.len() == 2 && country_code.bytes().all(|b| b.is_ascii_uppercase()))
+    {
+        errors.push(FieldError::new(
+            "country_code",
+            format!("must be an ISO 3166-1 alpha-2 code such as FR, got '{country_code}'"),
+        ));
+    }
+    if let Some(region) = region {
+        check_text(errors, "region", region);
+    }
+}
+
+fn check_latitude(errors: &mut Vec<FieldError>, latitude: f64) {
+    check_coordinate(errors, "latitude", latitude, 90.0);
+}
+
+fn check_longitude(errors: &mut Vec<FieldError>, longitude: f64) {
+    check_coordinate(errors, "longitude", longitude, 180.0);
+}
+
+/// Check that a coordinate is finite and within `[-bound, bound]`.
+fn check_coordinate(errors: &mut Vec<FieldError>, field: &str, value: f64, bound: f64) {
+    if !value.is_finite() || !(-bound..=bound).contains(&value) {
+        errors.push(FieldError::new(
+            field,
+            format!("must be between -{bound} and {bound}, got {value}"),
+        ));
+    }
+}
+
+fn check_pictures(errors: &mut Vec<FieldError>, field: &str, pictures: &[String]) {
+    for (index, picture) in pictures.iter().enumerate() {
+        if !is_valid_picture(picture) {
+            errors.push(FieldError::new(
+                format!("{field}[{index}]"),
+                format!("must be an http(s) URL or a path under {STATIC_PREFIX}, got '{picture}'"),
+            ));
+        }
+    }
+}
+
+/// Whether a picture is an absolute `http(s)` URL or a path below `/static/`.
+fn is_valid_picture(picture: &str) -> bool {
+    if let Some(path) = picture.strip_prefix(STATIC_PREFIX) {
+        return !path.is_empty()
+            && !path.contains(['\\', '?', '#'])
+            && path
+                .split('/')
+                .all(|segment| !segment.is_empty() && segment != "." && segment != "..");
+    }
+
+    url::Url::parse(picture)
+        .is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entities::sea_orm_active_enums::Visibility;
+
+    fn request() -> StickerRequest {
+        StickerRequest {
+            name: "Pont Neuf".to_string(),
+            latitude: 48.857,
+            longitude: 2.341,
+            place_name: "Paris".to_string(),
+            country_code: Some("FR".to_string()),
+            region: None,
+            pictures: vec!["/static/stickers/1/a.jpg".to_string()],
+            visibility: Visibility::default(),
+            placed_at: None,
+        }
+    }
+
+    /// Fields reported by a failed validation, in order.
+    fn invalid_fields(result: ApiResult<()>) -> Vec<String> {
+        match result {
+            Err(ApiError::InvalidFields(errors)) => {
+                errors.into_iter().map(|error| error.field).collect()
+            }
+            other => panic!("expected invalid fields, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn valid_request_passes() {
+        assert!(request().validate().is_ok());
+    }
+
+    #[test]
+    fn coordinates_must_be_within_bounds() {
+        for (latitude, longitude) in [(90.0, 180.0), (-90.0, -180.0), (0.0, 0.0)] {
+            let sticker = StickerRequest {
+                latitude,
+                longitude,
+                ..request()
+            };
+            assert!(sticker.validate().is_ok(), "{latitude}, {longitude}");
+        }
+
+        for latitude in [90.000_1, -91.0, f64::NAN, f64::INFINITY] {
+            let sticker = StickerRequest {
+                latitude,
+                ..request()
+            };
+            assert_eq!(invalid_fields(sticker.validate()), ["latitude"]);
+        }
+
+        for longitude in [180.000_1, -181.0, f64::NEG_INFINITY] {
+            let sticker = StickerRequest {
+                longitude,
+                ..request()
+            };
+            assert_eq!(invalid_fields(sticker.validate()), ["longitude"]);
+        }
+    }
+
+    #[test]
+    fn names_must_not_be_empty() {
+        let sticker = StickerRequest {
+            name: "   ".to_string(),
+            place_name: String::new(),
+            ..request()
+        };
+
+        assert_eq!(invalid_fields(sticker.validate()), ["name", "place_name"]);
+    }
+
+    #[test]
+    fn pictures_are_static_paths_or_web_urls() {
+        assert!(is_valid_picture("/static/stickers/1/a.jpg"));
+        assert!(is_valid_picture("https://example.com/a.jpg"));
+        assert!(is_valid_picture("http://example.com/a.jpg?size=large"));
+
+        assert!(!is_valid_picture("/static/../.env"));
+        assert!(!is_valid_picture("/static/stickers/../../secret.jpg"));
+        assert!(!is_valid_picture("/static/stickers//a.jpg"));
+        assert!(!is_valid_picture("/static/"));
+        assert!(!is_valid_picture("//evil.example.com/a.jpg"));
+        assert!(!is_valid_picture("ftp://example.com/a.jpg"));
+        assert!(!is_valid_picture("javascript:alert(1)"));
+        assert!(!is_valid_picture("photo.jpg"));
+    }
+
+    #[test]
+    fn every_invalid_field_is_reported_together() {
+        let sticker = StickerRequest {
+            name: String::new(),
+            latitude: 100.0,
+            longitude: 200.0,
+            country_code: Some("france".to_string()),
+            region: Some(" ".to_string()),
+            pictures: vec![
+                "/static/ok.jpg".to_string(),
+                "photo.jpg".to_string(),
+                "ftp://example.com/a.jpg".to_string(),
+            ],
+            ..request()
+        };
+
+        assert_eq!(
+            invalid_fields(sticker.validate()),
+            [
+                "name",
+                "country_code",
+                "region",
+                "latitude",
+                "longitude",
+                "pictures[1]",
+                "pictures[2]"
+            ]
+        );
+    }
+
+    #[test]
+    fn patch_only_checks_present_fields() {
+        assert!(StickerPatch::default().validate().is_ok());
+
+        let patch = StickerPatch {
+            latitude: Some(-95.0),
+            add_pictures: vec!["/static/../x.jpg".to_string()],
+            ..StickerPatch::default()
+        };
+        assert_eq!(
+            invalid_fields(patch.validate()),
+            ["latitude", "add_pictures[0]"]
+        );
+    }
+}
@@ -0,0 +1,560 @@
+//! Resized variants of uploaded sticker pictures
+//!
+//! Every picture stored by [`PictureStore`](super::pictures::PictureStore)
+//! gets a thumbnail and a few width variants next to it, so map popups and
+//! galleries don't download full-size phone photos:
+//!
+//! ```text
+//! stickers/<id>/<hash>.jpg         original
+//! stickers/<id>/<hash>_thumb.jpg   fits in 256x256
+//! stickers/<id>/<hash>_480w.jpg    480 pixels wide, if the original is wider
+//! stickers/<id>/<hash>_960w.jpg
+//! stickers/<id>/<hash>_1600w.jpg
+//! ```
+//!
+//! Variants are lossy JPEG, except for transparent PNG and WebP originals
+//! which get lossless WebP variants to keep their transparency. Pictures are
+//! never upscaled: only the widths smaller than the original get a variant.
+//!
+//! Which variants exist is read from the disk when describing a picture, so
+//! responses only advertise files that were actually written.
+
+use anyhow::Context;
+use image::{
+    DynamicImage, ImageDecoder, ImageFormat, ImageReader,
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::models::PictureResponse;
+
+/// Public path prefix of uploaded sticker pictures
+const PICTURES_URL: &str = "/static/stickers/";
+
+/// Bounding box of thumbnails, in pixels
+const THUMB_SIZE: u32 = 256;
+
+/// Maximum widths of the responsive variants, in pixels
+const WIDTHS: [u32; 3] = [480, 960, 1600];
+
+/// Quality of JPEG variants
+const JPEG_QUALITY: u8 = 80;
+
+/// Length of the content hash naming uploaded pictures
+const HASH_LENGTH: usize = 32;
+
+/// Extensions variants can be written with
+const VARIANT_EXTENSIONS: [&str; 2] = ["jpg", "webp"];
+
+/// Describe a picture with its variants.
+///
+/// Only pictures uploaded through the picture store have variants, the
+/// thumbnail of any other picture, or of one whose variants are not written
+/// yet, is `None`. The srcset lists the width variants, which only exist for
+/// widths smaller than the original, and is `None` when there are none.
+///
+/// Blocking, it checks which variant files exist, run it on a blocking thread.
+///
+/// # Arguments
+/// * `static_dir` - The directory served under `/static`.
+/// * `picture` - The picture path, as stored on the sticker.
+#[must_use]
+pub fn describe(static_dir: &Path, picture: &str) -> PictureResponse {
+    let Some(stem) = uploaded_stem(picture) else {
+        return PictureResponse {
+            original: picture.to_string(),
+            thumb: None,
+            srcset: None,
+        };
+    };
+
+    let relative = stem.strip_prefix(PICTURES_URL).unwrap_or_default();
+    let file = |suffix: &str, extension: &str| {
+        static_dir
+            .join("stickers")
+            .join(format!("{relative}_{suffix}.{extension}"))
+    };
+
+    // The thumbnail is always written, its extension is the one of every variant
+    let Some(extension) = VARIANT_EXTENSIONS
+        .into_iter()
+        .find(|extension| file("thumb", extension).exists())
+    else {
+        return PictureResponse {
+            original: picture.to_string(),
+            thumb: None,
+            srcset: None,
+        };
+    };
+
+    let srcset = WIDTHS
+        .iter()
+        .filter(|width| file(&format!("{width}w"), extension).exists())
+        .map(|width| format!("{stem}_{width}w.{extension} {width}w"))
+        .collect::<Vec<_>>();
+
+    PictureResponse {
+        original: picture.to_string(),
+        thumb: Some(format!("{stem}_thumb.{extension}")),
+        srcset: (!srcset.is_empty()).then(|| srcset.join(", ")),
+    }
+}
+
+/// Write the missing variants of an original picture.
+///
+/// Blocking, run it on a blocking thread.
+///
+/// # Errors
+/// Returns an error if the original cannot be decoded or a variant cannot be
+/// encoded or written.
+///
+/// Variants that no longer apply, such as width variants at least as wide as
+/// the original or variants in the other format, are deleted.
+pub fn generate(original: &Path) -> anyhow::Result<()> {
+    let Some(extension) = original.extension().and_then(|e| e.to_str()) else {
+        return Ok(());
+    };
+
+    let decoder = ImageReader::open(original)
+        .and_then(ImageReader::with_guessed_format)
+        .with_context(|| format!("Failed to open {}", original.display()))?
+        .into_decoder()
+        .with_context(|| format!("Failed to decode {}", original.display()))?;
+    let (width, _) = decoder.dimensions();
+    let has_alpha = decoder.color_type().has_alpha();
+    drop(decoder);
+
+    let mut image = None;
+
+    // An alpha channel does not make a picture transparent, only its pixels
+    // tell, so the thumbnail already written records the decision
+    let extension = if extension == "jpg" || !has_alpha {
+        "jpg"
+    } else if let Some(extension) = VARIANT_EXTENSIONS
+        .into_iter()
+        .find(|extension| sibling(original, "thumb", extension).exists())
+    {
+        extension
+    } else {
+        let decoded = decode(original)?;
+        let extension = if is_opaque(&decoded) { "jpg" } else { "webp" };
+        image = Some(decoded);
+        extension
+    };
+
+    let targets = std::iter::once(("thumb".to_string(), None))
+        .chain(
+            WIDTHS
+                .iter()
+                .filter(|variant_width| **variant_width < width)
+                .map(|width| (format!("{width}w"), Some(*width))),
+        )
+        .map(|(suffix, width)| (sibling(original, &suffix, extension), width))
+        .collect::<Vec<_>>();
+
+    remove_stale(original, &targets)?;
+
+    let missing = targets
+        .into_iter()
+        .filter(|(path, _)| !path.exists())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let image = match image {
+        Some(image) => image,
+        None => decode(original)?,
+    };
+    let format = if extension == "jpg" {
+        ImageFormat::Jpeg
+    } else {
+        ImageFormat::WebP
+    };
+
+    for (path, width) in missing {
+        let variant = match width {
+            // `thumbnail` also enlarges pictures smaller than the box
+            None if image.width() <= THUMB_SIZE && image.height() <= THUMB_SIZE => image.clone(),
+            None => image.thumbnail(THUMB_SIZE, THUMB_SIZE),
+            Some(width) => image.resize(width, u32::MAX, image::imageops::FilterType::Lanczos3),
+        };
+
+        let bytes = encode(&variant, format)
+            .with_context(|| format!("Failed to encode {}", path.display()))?;
+        fs::write(&path, bytes).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    Ok(())
+}
+
+/// Generate the missing variants of every uploaded picture.
+///
+/// Covers pictures uploaded before variants existed, or whose variants were
+/// deleted. Blocking, run it on a blocking thread.
+///
+/// # Errors
+/// Returns an error if the pictures directory cannot be listed. Pictures that
+/// fail are logged and skipped.
+pub fn backfill(static_dir: &str) -> anyhow::Result<()> {
+    let root = Path::new(static_dir).join("stickers");
+    if !root.exists() {
+        return Ok(());
+    }
+
+    let directories =
+        fs::read_dir(&root).with_context(|| format!("Failed to list {}", root.display()))?;
+    for directory in directories.flatten() {
+        let Ok(files) = fs::read_dir(directory.path()) else {
+            continue;
+        };
+        for file in files.flatten() {
+            let path = file.path();
+            if is_original(&path)
+                && let Err(e) = generate(&path)
+            {
+                tracing::warn!("Failed to generate variants of {}: {e:#}", path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete an original picture and its variants.
+///
+/// Blocking, run it on a blocking thread. Files that are not original
+/// pictures are left alone.
+///
+/// # Errors
+/// Returns an error if the directory cannot be listed or a file cannot be removed.
+pub fn remove(original: &Path) -> anyhow::Result<()> {
+    let (Some(directory), Some(stem)) = (
+        original.parent(),
+        original.file_stem().and_then(|s| s.to_str()),
+    ) else {
+        return Ok(());
+    };
+    if !is_original(original) {
+        return Ok(());
+    }
+
+    let variant_prefix = format!("{stem}_");
+    let files = fs::read_dir(directory)
+        .with_context(|| format!("Failed to list {}", directory.display()))?;
+    for file in files.flatten() {
+        let path = file.path();
+        let is_variant = file
+            .file_name()
+            .to_str()
+            .is_some_and(|name| name.starts_with(&variant_prefix));
+        if path == original || is_variant {
+            fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The path without extension of an uploaded picture.
+fn uploaded_stem(picture: &str) -> Option<&str> {
+    let (stem, extension) = picture.rsplit_once('.')?;
+    let (directory, hash) = stem.strip_prefix(PICTURES_URL)?.split_once('/')?;
+
+    let uploaded = !directory.is_empty()
+        && directory.bytes().all(|b| b.is_ascii_digit())
+        && is_hash(hash)
+        && matches!(extension, "jpg" | "png" | "webp");
+
+    uploaded.then_some(stem)
+}
+
+/// Whether a file is an original picture, named after its content hash.
+fn is_original(path: &Path) -> bool {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+
+    is_hash(stem) && matches!(extension, "jpg" | "png" | "webp")
+}
+
+fn is_hash(name: &str) -> bool {
+    name.len() == HASH_LENGTH && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn decode(original: &Path) -> anyhow::Result<DynamicImage> {
+    image::open(original).with_context(|| format!("Failed to decode {}", original.display()))
+}
+
+/// Whether every pixel of a picture is fully opaque.
+fn is_opaque(image: &DynamicImage) -> bool {
+    !image.color().has_alpha() || image.to_rgba8().pixels().all(|pixel| pixel[3] == u8::MAX)
+}
+
+/// Delete the variants of an original that are not among `targets`.
+fn remove_stale(original: &Path, targets: &[(PathBuf, Option<u32>)]) -> anyhow::Result<()> {
+    let suffixes =
+        std::iter::once("thumb".to_string()).chain(WIDTHS.iter().map(|width| format!("{width}w")));
+
+    for suffix in suffixes {
+        for extension in VARIANT_EXTENSIONS {
+            let path = sibling(original, &suffix, extension);
+            if !targets.iter().any(|(target, _)| *target == path) && path.exists() {
+                fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove {}", path.display()))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Path of a variant, next to its original.
+fn sibling(original: &Path, suffix: &str, extension: &str) -> PathBuf {
+    let stem = original
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or_default();
+    original.with_file_name(format!("{stem}_{suffix}.{extension}"))
+}
+
+/// Encode a variant, as lossy JPEG or lossless WebP.
+fn encode(image: &DynamicImage, format: ImageFormat) -> image::ImageResult<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match format {
+        ImageFormat::Jpeg => {
+            image
+                .to_rgb8()
+                .write_with_encoder(JpegEncoder::new_with_quality(&mut bytes, JPEG_QUALITY))?;
+        }
+        _ => {
+            image
+                .to_rgba8()
+                .write_with_encoder(WebPEncoder::new_lossless(&mut bytes))?;
+        }
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{RgbImage, RgbaImage};
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef";
+
+    /// A directory under the system temporary directory, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir()
+                .join(format!("sticker-variants-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(path.join("stickers/1")).unwrap();
+            Self(path)
+        }
+
+        fn file(&self, name: &str) -> PathBuf {
+            self.0.join("stickers/1").join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn write_jpeg(path: &Path, width: u32, height: u32) {
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .save_with_format(path, ImageFormat::Jpeg)
+            .unwrap();
+    }
+
+    fn width(path: &Path) -> u32 {
+        image::image_dimensions(path).unwrap().0
+    }
+
+    #[test]
+    fn only_uploaded_pictures_have_a_stem() {
+        let uploaded = format!("/static/stickers/12/{HASH}.jpg");
+        assert_eq!(
+            uploaded_stem(&uploaded),
+            Some(format!("/static/stickers/12/{HASH}").as_str())
+        );
+        assert!(uploaded_stem(&format!("/static/stickers/12/{HASH}.webp")).is_some());
+
+        assert_eq!(uploaded_stem("https://example.com/a.jpg"), None);
+        assert_eq!(uploaded_stem(&format!("/static/other/12/{HASH}.jpg")), None);
+        assert_eq!(
+            uploaded_stem(&format!("/static/stickers/x/{HASH}.jpg")),
+            None
+        );
+        assert_eq!(
+            uploaded_stem(&format!("/static/stickers//{HASH}.jpg")),
+            None
+        );
+        assert_eq!(uploaded_stem("/static/stickers/12/photo.jpg"), None);
+        assert_eq!(
+            uploaded_stem(&format!("/static/stickers/12/{HASH}.gif")),
+            None
+        );
+        assert_eq!(
+            uploaded_stem(&format!("/static/stickers/12/{HASH}_thumb.jpg")),
+            None
+        );
+    }
+
+    #[test]
+    fn only_hash_named_files_are_originals() {
+        assert!(is_original(Path::new(&format!("stickers/1/{HASH}.png"))));
+
+        assert!(!is_original(Path::new(&format!(
+            "stickers/1/{HASH}_thumb.jpg"
+        ))));
+        assert!(!is_original(Path::new(&format!("stickers/1/{HASH}.txt"))));
+        assert!(!is_original(Path::new(&format!("stickers/1/{HASH}"))));
+        assert!(!is_original(Path::new("stickers/1/photo.jpg")));
+        assert!(!is_original(Path::new(
+            "stickers/1/0123456789ABCDEFXYZ.jpg"
+        )));
+    }
+
+    #[test]
+    fn describe_only_advertises_written_files() {
+        let dir = TempDir::new("describe");
+        let picture = format!("/static/stickers/1/{HASH}.jpg");
+
+        let missing = describe(&dir.0, &picture);
+        assert_eq!(missing.original, picture);
+        assert_eq!(missing.thumb, None);
+        assert_eq!(missing.srcset, None);
+
+        fs::write(dir.file(&format!("{HASH}_thumb.jpg")), b"").unwrap();
+        let thumb_only = describe(&dir.0, &picture);
+        assert_eq!(
+            thumb_only.thumb,
+            Some(format!("/static/stickers/1/{HASH}_thumb.jpg"))
+        );
+        assert_eq!(thumb_only.srcset, None);
+
+        fs::write(dir.file(&format!("{HASH}_480w.jpg")), b"").unwrap();
+        fs::write(dir.file(&format!("{HASH}_1600w.jpg")), b"").unwrap();
+        // A width variant in the other format than the thumbnail is ignored
+        fs::write(dir.file(&format!("{HASH}_960w.webp")), b"").unwrap();
+        let described = describe(&dir.0, &picture);
+        assert_eq!(
+            described.srcset.as_deref(),
+            Some(
+                format!(
+                    "/static/stickers/1/{HASH}_480w.jpg 480w, /static/stickers/1/{HASH}_1600w.jpg 1600w"
+                )
+                .as_str()
+            )
+        );
+
+        let external = describe(&dir.0, "https://example.com/a.jpg");
+        assert_eq!(external.thumb, None);
+    }
+
+    #[test]
+    fn generate_never_upscales() {
+        let dir = TempDir::new("upscale");
+        let original = dir.file(&format!("{HASH}.jpg"));
+        write_jpeg(&original, 1000, 500);
+
+        generate(&original).unwrap();
+
+        let thumb = image::image_dimensions(dir.file(&format!("{HASH}_thumb.jpg"))).unwrap();
+        assert_eq!(thumb, (256, 128));
+        assert_eq!(width(&dir.file(&format!("{HASH}_480w.jpg"))), 480);
+        assert_eq!(width(&dir.file(&format!("{HASH}_960w.jpg"))), 960);
+        assert!(!dir.file(&format!("{HASH}_1600w.jpg")).exists());
+
+        let small = dir.file(&format!("{}.jpg", "f".repeat(HASH_LENGTH)));
+        write_jpeg(&small, 100, 80);
+        generate(&small).unwrap();
+        let thumb = dir.file(&format!("{}_thumb.jpg", "f".repeat(HASH_LENGTH)));
+        assert_eq!(image::image_dimensions(thumb).unwrap(), (100, 80));
+        assert!(
+            !dir.file(&format!("{}_480w.jpg", "f".repeat(HASH_LENGTH)))
+                .exists()
+        );
+    }
+
+    #[test]
+    fn generate_switches_variants_to_jpeg_for_jpeg_originals() {
+        let dir = TempDir::new("to-jpeg");
+        let original = dir.file(&format!("{HASH}.jpg"));
+        write_jpeg(&original, 600, 400);
+        for stale in ["thumb.webp", "480w.webp", "960w.jpg"] {
+            fs::write(dir.file(&format!("{HASH}_{stale}")), b"stale").unwrap();
+        }
+
+        generate(&original).unwrap();
+
+        assert!(dir.file(&format!("{HASH}_thumb.jpg")).exists());
+        assert!(dir.file(&format!("{HASH}_480w.jpg")).exists());
+        for stale in ["thumb.webp", "480w.webp", "960w.jpg"] {
+            assert!(!dir.file(&format!("{HASH}_{stale}")).exists(), "{stale}");
+        }
+    }
+
+    #[test]
+    fn generate_keeps_transparency_in_webp_variants() {
+        let dir = TempDir::new("to-webp");
+        let original = dir.file(&format!("{HASH}.png"));
+        DynamicImage::ImageRgba8(RgbaImage::new(600, 400))
+            .save_with_format(&original, ImageFormat::Png)
+            .unwrap();
+        fs::write(dir.file(&format!("{HASH}_480w.jpg")), b"stale").unwrap();
+
+        generate(&original).unwrap();
+
+        assert!(dir.file(&format!("{HASH}_thumb.webp")).exists());
+        assert!(dir.file(&format!("{HASH}_480w.webp")).exists());
+        assert!(!dir.file(&format!("{HASH}_480w.jpg")).exists());
+        assert!(!dir.file(&format!("{HASH}_thumb.jpg")).exists());
+    }
+
+    #[test]
+    fn remove_stale_keeps_only_the_targets() {
+        let dir = TempDir::new("stale");
+        let original = dir.file(&format!("{HASH}.png"));
+        let all = [
+            "thumb.jpg",
+            "thumb.webp",
+            "480w.jpg",
+            "480w.webp",
+            "1600w.webp",
+        ];
+        for variant in all {
+            fs::write(dir.file(&format!("{HASH}_{variant}")), b"").unwrap();
+        }
+        let unrelated = dir.file("notes.txt");
+        fs::write(&unrelated, b"").unwrap();
+
+        let targets = [
+            (sibling(&original, "thumb", "webp"), None),
+            (sibling(&original, "480w", "webp"), Some(480)),
+        ];
+        remove_stale(&original, &targets).unwrap();
+
+        let left: Vec<_> = all
+            .into_iter()
+            .filter(|variant| dir.file(&format!("{HASH}_{variant}")).exists())
+            .collect();
+        assert_eq!(left, ["thumb.webp", "480w.webp"]);
+        assert!(unrelated.exists());
+    }
+}
@@ -0,0 +1,126 @@
+//! Public tracking handlers
+
+use axum::{
+    Extension,
+    extract::{ConnectInfo, Path, Query},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use tracing::info;
+
+use super::RedirectAllowlist;
+use crate::{
+    error::{ApiError, ApiResult},
+    source::{tracker::SourceTracker, utm::Utm, validate},
+};
+
+/// A 1x1 transparent GIF
+const PIXEL_GIF: [u8; 43] = [
+    0x47, 0x49, 0x46, 0x38, 0x39, 0x61, 0x01, 0x00, 0x01, 0x00, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xff, 0xff, 0xff, 0x21, 0xf9, 0x04, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2c, 0x00, 0x00, 0x00, 0x00,
+    0x01, 0x00, 0x01, 0x00, 0x00, 0x02, 0x02, 0x44, 0x01, 0x00, 0x3b,
+];
+
+/// Query parameters of the tracking route
+#[derive(Debug, Deserialize)]
+pub struct TrackParams {
+    /// Redirect destination, required unless the pixel is requested
+    pub to: Option<String>,
+    /// UTM parameters recorded on the hit
+    #[serde(flatten)]
+    pub utm: Utm,
+}
+
+/// Handles public GET requests to the tracking path ("/track/:source").
+///
+/// * `/track/<source>.gif` increments `<source>` and returns a 1x1 transparent GIF.
+/// * `/track/<source>?to=<url>` increments `<source>` and redirects (302) to `<url>`,
+///   which must be on an allow-listed host.
+///
+/// `utm_*` query parameters and the hashed visitor are recorded on the hit. Counting failures are
+/// logged but do not prevent the pixel or redirect from being served.
+///
+/// # Errors
+/// Returns 400 if the destination is missing, invalid or not allow-listed.
+pub async fn track_source(
+    Extension(tracker): Extension<SourceTracker>,
+    Extension(allowlist): Extension<RedirectAllowlist>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Path(source): Path<String>,
+    Query(params): Query<TrackParams>,
+) -> ApiResult<Response> {
+    info!("GET `/track/{source}` endpoint called");
+
+    let utm = params.utm.or(Utm::default());
+    let peer = peer.map(|ConnectInfo(addr)| addr);
+
+    if let Some(source) = source.strip_suffix(".gif") {
+        count(&tracker, source, &utm, &headers, peer).await;
+
+        return Ok((
+            [
+                (header::CONTENT_TYPE, "image/gif"),
+                (
+                    header::CACHE_CONTROL,
+                    "no-cache, no-store, must-revalidate, private",
+                ),
+                (header::PRAGMA, "no-cache"),
+                (header::EXPIRES, "0"),
+            ],
+            PIXEL_GIF,
+        )
+            .into_response());
+    }
+
+    let to = params
+        .to
+        .ok_or_else(|| ApiError::validation("Missing `to` redirect destination"))?;
+
+    let destination = url::Url::parse(&to).map_err(|_| {
+        ApiError::validation(format!(
+            "Invalid redirect destination '{}'",
+            validate::echo(&to)
+        ))
+    })?;
+
+    if !allowlist.allows(&destination) {
+        return Err(ApiError::validation(format!(
+            "Redirect destination not allowed: '{}'",
+            validate::echo(&to)
+        )));
+    }
+
+    count(&tracker, &source, &utm, &headers, peer).await;
+
+    Ok((
+        StatusCode::FOUND,
+        [
+            (header::LOCATION, destination.as_str()),
+            (
+                header::CACHE_CONTROL,
+                "no-cache, no-store, must-revalidate, private",
+            ),
+        ],
+    )
+        .into_response())
+}
+
+/// Increments a source, logging instead of failing the request.
+async fn count(
+    tracker: &SourceTracker,
+    source: &str,
+    utm: &Utm,
+    headers: &HeaderMap,
+    peer: Option<SocketAddr>,
+) {
+    match tracker.record_hit(source, utm, headers, peer).await {
+        Ok(_) => {}
+        Err(ApiError::ValidationFailed(reason)) => {
+            tracing::warn!("Ignored tracking hit: {reason}");
+        }
+        Err(e) => tracing::error!("Failed to count tracking hit for '{source}': {e:#}"),
+    }
+}